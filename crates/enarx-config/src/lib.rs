@@ -41,6 +41,18 @@ kind = "stdout"
 [[files]]
 kind = "stderr"
 
+## A file becoming readable when the Keep is asked to shut down
+# [[files]]
+# kind = "shutdown"
+
+## A sealing key of the Keep, derived for a context
+# [[files]]
+# name = "KEY"
+# kind = "key"
+# policy = "measurement" # or policy = "signer"
+# context = "database"
+# svn = 1 # or the current security version of the Keep, if not set
+
 ## A listen socket
 # [[files]]
 # name = "LISTEN"
@@ -219,6 +231,31 @@ pub enum File {
         name: Option<FileName>,
     },
 
+    /// File descriptor becoming readable when the Keep is asked to shut down
+    #[serde(rename = "shutdown")]
+    Shutdown {
+        /// Name assigned to the file descriptor
+        name: Option<FileName>,
+    },
+
+    /// File descriptor reading a sealing key of the Keep
+    #[serde(rename = "key")]
+    Key {
+        /// Name assigned to the file descriptor
+        name: Option<FileName>,
+
+        /// What the key is bound to
+        #[serde(default)]
+        policy: KeyPolicy,
+
+        /// Context the key is derived for, so that different uses get different keys
+        #[serde(default)]
+        context: String,
+
+        /// Security version of the Keep the key is derived for, by default the current one
+        svn: Option<u16>,
+    },
+
     /// File descriptor of a TCP listen socket
     #[serde(rename = "listen")]
     Listen {
@@ -265,6 +302,8 @@ impl File {
             Self::Stdin { name } => name.as_deref().unwrap_or("stdin"),
             Self::Stdout { name } => name.as_deref().unwrap_or("stdout"),
             Self::Stderr { name } => name.as_deref().unwrap_or("stderr"),
+            Self::Shutdown { name } => name.as_deref().unwrap_or("shutdown"),
+            Self::Key { name, .. } => name.as_deref().unwrap_or("key"),
             Self::Listen { name, .. } => name,
             Self::Connect { name, host, .. } => name.as_deref().unwrap_or(host),
         }
@@ -289,6 +328,26 @@ impl Default for Protocol {
     }
 }
 
+/// What a sealing key is bound to
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyPolicy {
+    /// The measurement of the Keep, so that only the same Keep gets the key
+    #[serde(rename = "measurement")]
+    Measurement,
+
+    /// The signer of the Keep, so that other Keeps of the same signer get the key
+    ///
+    /// Only supported on SGX.
+    #[serde(rename = "signer")]
+    Signer,
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        Self::Measurement
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -315,6 +374,15 @@ mod test {
         [[files]]
         kind = "connect"
         host = "example.com"
+
+        [[files]]
+        kind = "shutdown"
+
+        [[files]]
+        kind = "key"
+        policy = "signer"
+        context = "db"
+        svn = 1
     "#;
 
     #[test]
@@ -340,6 +408,13 @@ mod test {
                     prot: Protocol::Tls,
                     host: "example.com".into(),
                 },
+                File::Shutdown { name: None },
+                File::Key {
+                    name: None,
+                    policy: KeyPolicy::Signer,
+                    context: "db".into(),
+                    svn: Some(1),
+                },
             ]
         );
 
@@ -351,7 +426,16 @@ mod test {
         let cfg: Config = toml::from_str(CONFIG).unwrap();
 
         assert_eq!(
-            vec!["stdin", "X", "stdout", "null", "stderr", "example.com"],
+            vec![
+                "stdin",
+                "X",
+                "stdout",
+                "null",
+                "stderr",
+                "example.com",
+                "shutdown",
+                "key"
+            ],
             cfg.files.iter().map(|f| f.name()).collect::<Vec<_>>()
         );
     }
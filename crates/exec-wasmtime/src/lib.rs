@@ -7,10 +7,14 @@
 #![warn(rust_2018_idioms)]
 
 mod loader;
+#[cfg(unix)]
+mod shutdown;
 
 use loader::Loader;
 use url::Url;
 
+use std::collections::BTreeMap;
+
 #[cfg(unix)]
 use std::os::unix::io::{FromRawFd, RawFd};
 
@@ -62,6 +66,18 @@ pub enum Package {
 #[cfg_attr(unix, derive(Deserialize, Serialize))]
 #[repr(C)]
 pub struct Args {
+    /// Additional PEM-encoded root certificates to trust when fetching a remote package
+    pub ca_bundle: Option<String>,
+
+    /// Allow sealing keys derived from a public value on platforms without a key, like `kvm`
+    #[cfg_attr(unix, serde(default))]
+    pub insecure_keys: bool,
+
+    /// Content digest of the root tree of a remote package, mapping algorithms
+    /// like `sha-256` to base64-encoded hashes, which the fetched package must match
+    #[cfg_attr(unix, serde(default))]
+    pub package_digest: Option<BTreeMap<String, String>>,
+
     /// Package
     pub package: Package,
 }
@@ -86,22 +102,25 @@ pub fn execute_with_args(args: Args) -> anyhow::Result<()> {
 pub fn execute() -> anyhow::Result<()> {
     use anyhow::Context;
     use std::io::Read;
-    use std::mem::forget;
     use std::os::unix::net::UnixStream;
 
-    // This is the FD of a Unix socket on which the host will send the TOML-encoded execution arguments
-    // and shutdown the write half of it immediately after.
+    // This is the FD of a Unix socket on which the host will send the length-prefixed,
+    // TOML-encoded execution arguments. The host keeps the socket open afterwards,
+    // to signal a shutdown request to the workload.
     // TODO: Use the write half of the socket to write logs/errors to the host
     let mut host = unsafe { UnixStream::from_raw_fd(3) };
 
-    let mut args = String::new();
-    host.read_to_string(&mut args)
+    let mut len = [0u8; 8];
+    host.read_exact(&mut len)
+        .context("failed to read arguments length")?;
+    let mut args = vec![0u8; u64::from_le_bytes(len) as _];
+    host.read_exact(&mut args)
         .context("failed to read arguments")?;
 
-    // The FD is managed by the host or its parent.
-    forget(host);
+    // The FD is kept open for the lifetime of the Keep.
+    shutdown::set_host(host);
 
-    let args = toml::from_str::<Args>(&args).context("failed to decode arguments")?;
+    let args = toml::from_slice::<Args>(&args).context("failed to decode arguments")?;
 
     execute_with_args(args)?;
 
@@ -146,6 +165,24 @@ mod test {
       (data (i32.const 0) "Hello, world!\0a")
     )"#;
 
+    const ATTESTATION_WAT: &str = r#"(module
+      (import "enarx:attestation" "technology"
+        (func $technology (result i32)))
+      (import "enarx:attestation" "attest"
+        (func $attest (param i32 i32 i32 i32) (result i32)))
+      (func (export "") (result i32 i32)
+        (call $technology)
+        (call $attest
+          (i32.const 0)
+          (i32.const 5)
+          (i32.const 16)
+          (i32.const 4096))
+      )
+      (memory 1)
+      (export "memory" (memory 0))
+      (data (i32.const 0) "nonce")
+    )"#;
+
     #[test]
     fn workload_run_return_1() {
         let bytes = wat::parse_str(RETURN_1_WAT).expect("error parsing wat");
@@ -178,4 +215,18 @@ mod test {
         // TODO/FIXME: we need a way to configure WASI stdout so we can capture
         // and check it here...
     }
+
+    #[test]
+    fn workload_run_attestation() {
+        let bytes = wat::parse_str(ATTESTATION_WAT).expect("error parsing wat");
+
+        let results: Vec<i32> = Loader::run(&bytes)
+            .unwrap()
+            .iter()
+            .map(wasmtime::Val::unwrap_i32)
+            .collect();
+
+        // Outside of a Keep the technology is `kvm` without any evidence.
+        assert_eq!(results, vec![0, 0]);
+    }
 }
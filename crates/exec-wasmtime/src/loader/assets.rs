@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: Apache-2.0
+//! Read-only, in-memory package assets exposed to the workload as a preopened directory
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::io::{IoSliceMut, Read, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use wasi_common::clocks::SystemTimeSpec;
+use wasi_common::dir::{ReaddirCursor, ReaddirEntity};
+use wasi_common::file::{FdFlags, FileType, Filestat, OFlags};
+use wasi_common::{Error, ErrorExt, WasiDir, WasiFile};
+
+/// Path at which the package assets are preopened
+pub const ASSETS_PATH: &str = "/";
+
+/// A node of the asset tree
+#[derive(Debug)]
+pub enum Node {
+    File(Arc<[u8]>),
+    Directory(Arc<Directory>),
+}
+
+impl Node {
+    fn filestat(&self) -> Filestat {
+        let (filetype, size) = match self {
+            Self::File(data) => (FileType::RegularFile, data.len() as _),
+            Self::Directory(..) => (FileType::Directory, 0),
+        };
+        Filestat {
+            device_id: 0,
+            inode: 0,
+            filetype,
+            nlink: 1,
+            size,
+            atim: None,
+            mtim: None,
+            ctim: None,
+        }
+    }
+}
+
+/// A directory of the asset tree
+#[derive(Debug, Default)]
+pub struct Directory(BTreeMap<String, Node>);
+
+impl FromIterator<(String, Node)> for Directory {
+    fn from_iter<T: IntoIterator<Item = (String, Node)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Directory {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn lookup(self: &Arc<Self>, path: &str) -> Result<Node, Error> {
+        let mut node = Node::Directory(self.clone());
+        for name in path
+            .split('/')
+            .filter(|&name| !name.is_empty() && name != ".")
+        {
+            let dir = match node {
+                Node::Directory(ref dir) => dir,
+                Node::File(..) => return Err(Error::not_dir()),
+            };
+            if name == ".." {
+                return Err(Error::not_supported().context("cannot leave the package assets"));
+            }
+            node = match dir.0.get(name) {
+                Some(Node::File(data)) => Node::File(data.clone()),
+                Some(Node::Directory(dir)) => Node::Directory(dir.clone()),
+                None => return Err(Error::not_found()),
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// A preopened asset directory
+pub struct Dir(pub Arc<Directory>);
+
+/// The error of every operation modifying the package assets
+fn read_only() -> Error {
+    Error::not_supported().context("package assets are read-only")
+}
+
+#[wiggle::async_trait]
+impl WasiDir for Dir {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn open_file(
+        &self,
+        _symlink_follow: bool,
+        path: &str,
+        oflags: OFlags,
+        _read: bool,
+        write: bool,
+        _fdflags: FdFlags,
+    ) -> Result<Box<dyn WasiFile>, Error> {
+        if write || oflags.intersects(OFlags::CREATE | OFlags::TRUNCATE) {
+            return Err(read_only());
+        }
+        match self.0.lookup(path)? {
+            Node::File(data) => Ok(Box::new(File { data, pos: 0 })),
+            Node::Directory(..) => Err(Error::not_supported()),
+        }
+    }
+
+    async fn open_dir(&self, _symlink_follow: bool, path: &str) -> Result<Box<dyn WasiDir>, Error> {
+        match self.0.lookup(path)? {
+            Node::Directory(dir) => Ok(Box::new(Dir(dir))),
+            Node::File(..) => Err(Error::not_dir()),
+        }
+    }
+
+    async fn create_dir(&self, _path: &str) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn readdir(
+        &self,
+        cursor: ReaddirCursor,
+    ) -> Result<Box<dyn Iterator<Item = Result<ReaddirEntity, Error>> + Send>, Error> {
+        let dots = [".", ".."]
+            .into_iter()
+            .map(|name| (name.to_string(), FileType::Directory));
+        let entries = self.0 .0.iter().map(|(name, node)| {
+            let filetype = match node {
+                Node::File(..) => FileType::RegularFile,
+                Node::Directory(..) => FileType::Directory,
+            };
+            (name.clone(), filetype)
+        });
+        // The returned iterator must not borrow the directory.
+        #[allow(clippy::needless_collect)]
+        let entries: Vec<_> = dots
+            .chain(entries)
+            .enumerate()
+            .skip(u64::from(cursor) as _)
+            .map(|(i, (name, filetype))| {
+                Ok(ReaddirEntity {
+                    next: ReaddirCursor::from(i as u64 + 1),
+                    inode: 0,
+                    name,
+                    filetype,
+                })
+            })
+            .collect();
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    async fn symlink(&self, _old_path: &str, _new_path: &str) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn remove_dir(&self, _path: &str) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn unlink_file(&self, _path: &str) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn read_link(&self, _path: &str) -> Result<PathBuf, Error> {
+        Err(Error::not_supported().context("package assets have no symbolic links"))
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        Ok(Node::Directory(self.0.clone()).filestat())
+    }
+
+    async fn get_path_filestat(
+        &self,
+        path: &str,
+        _follow_symlinks: bool,
+    ) -> Result<Filestat, Error> {
+        self.0.lookup(path).map(|node| node.filestat())
+    }
+
+    async fn rename(
+        &self,
+        _path: &str,
+        _dest_dir: &dyn WasiDir,
+        _dest_path: &str,
+    ) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn hard_link(
+        &self,
+        _path: &str,
+        _target_dir: &dyn WasiDir,
+        _target_path: &str,
+    ) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn set_times(
+        &self,
+        _path: &str,
+        _atime: Option<SystemTimeSpec>,
+        _mtime: Option<SystemTimeSpec>,
+        _follow_symlinks: bool,
+    ) -> Result<(), Error> {
+        Err(read_only())
+    }
+}
+
+/// An open asset file
+pub struct File {
+    data: Arc<[u8]>,
+    pos: u64,
+}
+
+impl File {
+    fn remaining(&self, offset: u64) -> &[u8] {
+        let offset = offset.min(self.data.len() as _) as usize;
+        &self.data[offset..]
+    }
+}
+
+#[wiggle::async_trait]
+impl WasiFile for File {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn get_filetype(&mut self) -> Result<FileType, Error> {
+        Ok(FileType::RegularFile)
+    }
+
+    async fn get_filestat(&mut self) -> Result<Filestat, Error> {
+        Ok(Node::File(self.data.clone()).filestat())
+    }
+
+    async fn read_vectored<'a>(&mut self, bufs: &mut [IoSliceMut<'a>]) -> Result<u64, Error> {
+        let n = self.remaining(self.pos).read_vectored(bufs)? as u64;
+        self.pos += n;
+        Ok(n)
+    }
+
+    async fn read_vectored_at<'a>(
+        &mut self,
+        bufs: &mut [IoSliceMut<'a>],
+        offset: u64,
+    ) -> Result<u64, Error> {
+        Ok(self.remaining(offset).read_vectored(bufs)? as _)
+    }
+
+    async fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => pos as i128,
+            SeekFrom::End(off) => self.data.len() as i128 + off as i128,
+            SeekFrom::Current(off) => self.pos as i128 + off as i128,
+        };
+        self.pos = pos.try_into().map_err(|_| Error::invalid_argument())?;
+        Ok(self.pos)
+    }
+
+    async fn peek(&mut self, buf: &mut [u8]) -> Result<u64, Error> {
+        Ok(self.remaining(self.pos).read(buf)? as _)
+    }
+
+    async fn num_ready_bytes(&self) -> Result<u64, Error> {
+        Ok(self.remaining(self.pos).len() as _)
+    }
+
+    async fn readable(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tree() -> Arc<Directory> {
+        let model: Directory = [(
+            "weights.bin".to_string(),
+            Node::File(b"0123".as_ref().into()),
+        )]
+        .into_iter()
+        .collect();
+        Arc::new(
+            [
+                ("data.txt".to_string(), Node::File(b"hello".as_ref().into())),
+                ("model".to_string(), Node::Directory(Arc::new(model))),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn lookup() {
+        let tree = tree();
+        assert!(matches!(tree.lookup("data.txt"), Ok(Node::File(data)) if &*data == b"hello"));
+        assert!(
+            matches!(tree.lookup("./model/weights.bin"), Ok(Node::File(data)) if &*data == b"0123")
+        );
+        assert!(matches!(tree.lookup("model/"), Ok(Node::Directory(..))));
+        assert!(matches!(tree.lookup(""), Ok(Node::Directory(..))));
+        assert!(tree.lookup("missing").is_err());
+        assert!(tree.lookup("data.txt/x").is_err());
+        assert!(tree.lookup("model/../data.txt").is_err());
+    }
+}
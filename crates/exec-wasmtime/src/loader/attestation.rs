@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `enarx:attestation` host module
+//!
+//! This module lets workloads request attestation evidence over their own
+//! report data, for instance a nonce from a remote verifier. It exports:
+//!
+//!   * `technology() -> i32`: the technology of the Keep, `0` for `kvm` and
+//!     `nil`, `1` for `snp` and `2` for `sgx`.
+//!
+//!   * `attest(data: i32, data_len: i32, buf: i32, buf_len: i32) -> i32`:
+//!     produces evidence over at most 64 bytes of workload data and returns
+//!     its length. The evidence is only written to `buf` if it fits in
+//!     `buf_len` bytes. On `snp` the evidence contains the report and the
+//!     VCEK, on `sgx` the quote with its certification data, and it is empty
+//!     on `kvm` and `nil`.
+//!
+//! The report data of the evidence is not the workload data itself, but
+//! `SHA-512("enarx workload attestation\0" || data)`. This keeps it apart from
+//! the report data of the certification request of the Keep, the zero padded
+//! hash of its public key, so that a workload cannot produce evidence which
+//! passes for the Keep's own. Verifiers must recompute this hash from the data
+//! they expect, e.g. with `enarx_verifier::workload_report_data`, and compare
+//! it with the report data of the evidence.
+
+use super::configured::platform::{Platform, Technology};
+
+use anyhow::Result;
+use sha2::{Digest, Sha512};
+use wasmtime::{Caller, Extern, Linker, Trap};
+
+/// The name of the host module
+const MODULE: &str = "enarx:attestation";
+
+/// The maximum length of the workload data
+const DATA_LEN: usize = 64;
+
+/// The domain separation tag prepended to the workload data before hashing
+const TAG: &[u8] = b"enarx workload attestation\0";
+
+/// Add the `enarx:attestation` host module to the linker.
+pub fn add_to_linker<T>(linker: &mut Linker<T>) -> Result<()> {
+    let platform = Platform::get()?;
+
+    linker.func_wrap(MODULE, "technology", move || -> i32 {
+        match platform.technology() {
+            Technology::Kvm => 0,
+            Technology::Snp => 1,
+            Technology::Sgx => 2,
+        }
+    })?;
+
+    linker.func_wrap(
+        MODULE,
+        "attest",
+        move |mut caller: Caller<'_, T>,
+              data: u32,
+              data_len: u32,
+              buf: u32,
+              buf_len: u32|
+              -> Result<u32, Trap> {
+            let memory = match caller.get_export("memory").and_then(Extern::into_memory) {
+                Some(memory) => memory,
+                None => return Err(Trap::new("Failed to find the exported memory")),
+            };
+
+            if data_len as usize > DATA_LEN {
+                return Err(Trap::new(format!(
+                    "Workload data is {} bytes, more than the maximum of {} bytes",
+                    data_len, DATA_LEN
+                )));
+            }
+
+            let mut workload_data = [0u8; DATA_LEN];
+            let workload_data = &mut workload_data[..data_len as _];
+            memory
+                .read(&caller, data as _, workload_data)
+                .map_err(|e| Trap::new(format!("Failed to read the workload data: {}", e)))?;
+
+            let report_data = Sha512::new()
+                .chain_update(TAG)
+                .chain_update(workload_data)
+                .finalize();
+
+            let evidence = platform
+                .attest(&report_data)
+                .map_err(|e| Trap::new(format!("Failed to attest: {}", e)))?;
+
+            if evidence.len() <= buf_len as usize {
+                memory
+                    .write(&mut caller, buf as _, &evidence)
+                    .map_err(|e| Trap::new(format!("Failed to write the evidence: {}", e)))?;
+            }
+
+            Ok(evidence.len() as _)
+        },
+    )?;
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{Attested, Compiled, Loader};
+use super::{attestation, Attested, Compiled, Loader};
 
 use anyhow::Result;
 use wasmtime_wasi::WasiCtxBuilder;
@@ -18,9 +18,10 @@ impl Loader<Attested> {
         // Create the execution engine.
         let engine = wasmtime::Engine::new(&config)?;
 
-        // Set up the linker and add WASI.
+        // Set up the linker and add WASI and attestation.
         let mut linker = wasmtime::Linker::new(&engine);
         wasmtime_wasi::add_to_linker(&mut linker, |s| s)?;
+        attestation::add_to_linker(&mut linker)?;
 
         // Create the store.
         let mut wstore = wasmtime::Store::new(&engine, WasiCtxBuilder::new().build());
@@ -33,6 +34,8 @@ impl Loader<Attested> {
             srvcfg: self.0.srvcfg,
             cltcfg: self.0.cltcfg,
             config: self.0.config,
+            assets: self.0.assets,
+            insecure_keys: self.0.insecure_keys,
             wstore,
             linker,
         }))
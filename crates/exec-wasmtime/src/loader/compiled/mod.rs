@@ -5,11 +5,14 @@ mod tls;
 
 use null::Null;
 
+use super::assets::{Dir, ASSETS_PATH};
+use super::configured::platform::Platform;
 use super::{Compiled, Connected, Loader};
 
 use anyhow::{Context, Result};
 use cap_std::net::{TcpListener, TcpStream};
 use enarx_config::{File, Protocol};
+use wasi_common::pipe::ReadPipe;
 use wasi_common::{file::FileCaps, WasiFile};
 use wasmtime::AsContextMut;
 use wasmtime_wasi::stdio::{stderr, stdin, stdout};
@@ -47,6 +50,35 @@ impl Loader<Compiled> {
                 File::Stdout { .. } => (Box::new(stdout()), FileCaps::all()),
                 File::Stderr { .. } => (Box::new(stderr()), FileCaps::all()),
 
+                #[cfg(unix)]
+                File::Shutdown { .. } => {
+                    let caps = FileCaps::FILESTAT_GET
+                        | FileCaps::FDSTAT_SET_FLAGS
+                        | FileCaps::POLL_READWRITE
+                        | FileCaps::READ;
+
+                    (crate::shutdown::file()?, caps)
+                }
+
+                #[cfg(windows)]
+                File::Shutdown { .. } => {
+                    anyhow::bail!("shutdown notifications are not supported on this platform")
+                }
+
+                File::Key {
+                    policy,
+                    context,
+                    svn,
+                    ..
+                } => {
+                    let caps = FileCaps::FILESTAT_GET | FileCaps::POLL_READWRITE | FileCaps::READ;
+
+                    let insecure = self.0.insecure_keys;
+                    let key = Platform::key(*policy, *svn, context.as_bytes(), insecure)
+                        .context("failed to derive sealing key")?;
+                    (Box::new(ReadPipe::from(key.to_vec())), caps)
+                }
+
                 File::Listen {
                     addr, port, prot, ..
                 } => {
@@ -90,6 +122,16 @@ impl Loader<Compiled> {
             ctx.insert_file(fd.try_into().unwrap(), file, caps);
         }
 
+        // Let the host know if the workload does not listen for shutdown requests.
+        #[cfg(unix)]
+        crate::shutdown::close_unused();
+
+        // Preopen the package assets after all the file descriptors, so that they do not clash.
+        if !self.0.assets.is_empty() {
+            ctx.push_preopened_dir(Box::new(Dir(self.0.assets)), ASSETS_PATH)
+                .context("failed to preopen package assets")?;
+        }
+
         Ok(Loader(Connected {
             wstore: self.0.wstore,
             linker: self.0.linker,
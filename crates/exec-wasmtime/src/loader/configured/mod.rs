@@ -2,7 +2,7 @@
 
 #![allow(dead_code)]
 
-mod platform;
+pub(super) mod platform;
 
 #[allow(unused_imports)]
 use platform::{Platform, Technology};
@@ -90,6 +90,9 @@ impl Loader<Configured> {
         let req = Self::make_csr(&pki, ext)?;
 
         Ok(Loader(Requested {
+            ca_bundle: self.0.args.ca_bundle,
+            insecure_keys: self.0.args.insecure_keys,
+            package_digest: self.0.args.package_digest,
             package: self.0.args.package,
             prvkey: raw,
             crtreq: req,
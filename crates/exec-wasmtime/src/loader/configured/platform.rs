@@ -1,8 +1,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::io::{ErrorKind, Result};
+use std::io::{Error, ErrorKind, Result};
 
 use const_oid::ObjectIdentifier;
+use enarx_config::KeyPolicy;
+use ring::hkdf;
+use zeroize::Zeroizing;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Technology {
@@ -32,6 +35,13 @@ pub struct Platform(Technology, usize);
 
 impl Platform {
     const SYS_GETATT: usize = 0xEA01;
+    const SYS_GETKEY: usize = 0xEA02;
+
+    /// Security version deriving the key for the current one of the Keep
+    const KEY_SVN_CURRENT: usize = usize::MAX;
+
+    /// Salt of the sealing keys derived from the key of the platform
+    const KEY_SALT: &'static [u8] = b"enarx sealing key";
 
     #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
     fn get_att(_nonce: Option<&[u8]>, _buf: Option<&mut [u8]>) -> Result<Self> {
@@ -76,6 +86,84 @@ impl Platform {
         }
     }
 
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    fn get_key(_policy: usize, _svn: usize, _buf: &mut [u8]) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    fn get_key(policy: usize, svn: usize, buf: &mut [u8]) -> Result<Option<usize>> {
+        use std::arch::asm;
+
+        const ENOSYS: isize = -(libc::ENOSYS as isize);
+        const EPERM: isize = -(libc::EPERM as isize);
+
+        let mut rax: isize;
+
+        unsafe {
+            asm!(
+                "syscall",
+                lateout("rax") rax,
+                lateout("rdx") _, // technology
+                in("rax") Self::SYS_GETKEY,
+                in("rdi") policy,
+                in("rsi") buf.as_mut_ptr(),
+                in("rdx") buf.len(),
+                in("r10") svn,
+                lateout("rcx") _, // clobbered
+                lateout("r11") _, // clobbered
+            )
+        }
+
+        match rax {
+            ENOSYS | EPERM => Ok(None),
+            n if n < 0 => Err(std::io::Error::from_raw_os_error(-n as i32)),
+            n => Ok(Some(n as _)),
+        }
+    }
+
+    /// Derive a sealing key of the Keep bound to `policy` and the security
+    /// version `svn`, by default the current one, for `context`.
+    ///
+    /// Without a key of the platform, as on `kvm` and `nil`, the key is
+    /// derived from a fixed value and is not secret, so it is refused unless
+    /// `insecure` is set.
+    pub fn key(
+        policy: KeyPolicy,
+        svn: Option<u16>,
+        context: &[u8],
+        insecure: bool,
+    ) -> Result<Zeroizing<[u8; 32]>> {
+        let policy = match policy {
+            KeyPolicy::Measurement => 0,
+            KeyPolicy::Signer => 1,
+        };
+        let svn = svn.map_or(Self::KEY_SVN_CURRENT, Into::into);
+
+        let mut root = Zeroizing::new([0u8; 32]);
+        let len = match Self::get_key(policy, svn, &mut root[..])? {
+            Some(len) => len,
+            None if insecure => 0,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "the platform has no sealing key, insecure keys must be explicitly allowed",
+                ))
+            }
+        };
+        if len > root.len() {
+            return Err(ErrorKind::Other.into());
+        }
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        hkdf::Salt::new(hkdf::HKDF_SHA256, Self::KEY_SALT)
+            .extract(&root[..len])
+            .expand(&[&[policy as u8], context], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut key[..]))
+            .map_err(|_| ErrorKind::Other)?;
+        Ok(key)
+    }
+
     pub fn get() -> Result<Self> {
         Self::get_att(None, None)
     }
@@ -105,3 +193,23 @@ fn test() {
     let report = platform.attest(b"00000000").unwrap();
     assert!(report.is_empty());
 }
+
+#[test]
+fn key() {
+    let key = |policy, context: &[u8]| *Platform::key(policy, None, context, true).unwrap();
+    assert_eq!(
+        key(KeyPolicy::Measurement, b"context"),
+        key(KeyPolicy::Measurement, b"context")
+    );
+    assert_ne!(
+        key(KeyPolicy::Measurement, b"context"),
+        key(KeyPolicy::Measurement, b"other")
+    );
+    assert_ne!(
+        key(KeyPolicy::Measurement, b"context"),
+        key(KeyPolicy::Signer, b"context")
+    );
+
+    // Without a key of the platform, insecure keys must be allowed.
+    assert!(Platform::key(KeyPolicy::Measurement, None, b"context", false).is_err());
+}
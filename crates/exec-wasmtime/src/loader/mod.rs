@@ -9,6 +9,8 @@
 //!
 //! The types are defined in sequential order.
 
+mod assets;
+mod attestation;
 mod attested;
 mod compiled;
 mod configured;
@@ -17,7 +19,9 @@ mod pki;
 mod requested;
 
 use super::{Args, Package};
+use assets::Directory;
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use enarx_config::Config;
@@ -33,17 +37,22 @@ pub struct Configured {
 
 /// The second state, indicating that a CSR has been generated
 pub struct Requested {
+    ca_bundle: Option<String>,
+    insecure_keys: bool,
+    package_digest: Option<BTreeMap<String, String>>,
     package: Package,
     prvkey: Zeroizing<Vec<u8>>,
     crtreq: Vec<u8>,
 }
 
-/// The third state, indicating receipt of the configuration, certificate, WASM module and configuration
+/// The third state, indicating receipt of the configuration, certificate, WASM module, configuration and assets
 pub struct Attested {
     srvcfg: Arc<ServerConfig>,
     cltcfg: Arc<ClientConfig>,
     config: Config,
     webasm: Vec<u8>,
+    assets: Arc<Directory>,
+    insecure_keys: bool,
 }
 
 /// The fifth state, indicating compilation of the WASM module
@@ -51,6 +60,8 @@ pub struct Compiled {
     srvcfg: Arc<ServerConfig>,
     cltcfg: Arc<ClientConfig>,
     config: Config,
+    assets: Arc<Directory>,
+    insecure_keys: bool,
     wstore: Store<WasiCtx>,
     linker: Linker<WasiCtx>,
 }
@@ -99,6 +110,8 @@ impl Loader<Attested> {
             cltcfg: Arc::new(cltcfg),
             config: Default::default(),
             webasm: module.to_vec(),
+            assets: Default::default(),
+            insecure_keys: false,
         });
 
         let compiled = attested.next()?;
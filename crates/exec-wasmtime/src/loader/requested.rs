@@ -1,9 +1,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::super::{Package, PACKAGE_CONFIG, PACKAGE_ENTRYPOINT};
+use super::assets::{Directory, Node};
 use super::pki::PrivateKeyInfoExt;
 use super::{Attested, Loader, Requested};
 
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::ops::Deref;
 
@@ -20,8 +22,10 @@ use const_oid::db::rfc5280::{
 use enarx_config::Config;
 use getrandom::getrandom;
 use pkcs8::PrivateKeyInfo;
+use ring::digest::{digest, SHA256, SHA384, SHA512};
 use rustls::{cipher_suite::*, kx_group::*, version::TLS13, *};
-use ureq::Response;
+use serde::Deserialize;
+use ureq::{Agent, AgentBuilder, Response};
 use url::Url;
 use x509_cert::der::asn1::{BitStringRef, UIntRef};
 use x509_cert::der::{Decode, Encode};
@@ -33,19 +37,52 @@ use x509_cert::{Certificate, PkiPath, TbsCertificate};
 /// Maximum size of WASM module in bytes
 const MAX_WASM_SIZE: u64 = 10_000_000;
 
+/// Maximum size of the package config in bytes
+const MAX_CONFIG_SIZE: u64 = 1_000_000;
+
+/// Maximum combined size of the directory listings and assets of a package in bytes
+const MAX_ASSETS_SIZE: u64 = 100_000_000;
+
 const DRAWBRIDGE_DIRECTORY_MEDIA_TYPE: &str = "application/vnd.drawbridge.directory.v1+json";
 const TOML_MEDIA_TYPE: &str = "application/toml";
 const WASM_MEDIA_TYPE: &str = "application/wasm";
 
-fn get(url: impl AsRef<str>) -> Result<Response> {
+/// Construct an agent trusting the WebPKI roots and the certificates in `ca_bundle`
+fn agent(ca_bundle: Option<&str>) -> Result<Agent> {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    if let Some(ca_bundle) = ca_bundle {
+        for cert in
+            rustls_pemfile::certs(&mut ca_bundle.as_bytes()).context("failed to parse CA bundle")?
+        {
+            root_store
+                .add(&rustls::Certificate(cert))
+                .map_err(|e| anyhow!("failed to add CA certificate: {e:?}"))?;
+        }
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    Ok(AgentBuilder::new().tls_config(Arc::new(config)).build())
+}
+
+fn get(agent: &Agent, url: impl AsRef<str>) -> Result<Response> {
     let url = url.as_ref();
-    ureq::get(url)
+    agent
+        .get(url)
         .call()
         .with_context(|| format!("failed to GET `{url}`"))
 }
 
-fn get_typed(typ: &str, url: impl AsRef<str>) -> Result<Response> {
-    get(url).and_then(|res| {
+fn get_typed(agent: &Agent, typ: &str, url: impl AsRef<str>) -> Result<Response> {
+    get(agent, url).and_then(|res| {
         let ct = res.content_type();
         ensure!(
             ct == typ,
@@ -55,29 +92,146 @@ fn get_typed(typ: &str, url: impl AsRef<str>) -> Result<Response> {
     })
 }
 
-fn response_into_wasm(res: Response) -> Result<Vec<u8>> {
-    // TODO: Initialize with capacity of Content-Length if set.
-    let mut wasm = Vec::new();
+/// A content digest, mapping algorithms like `sha-256` to base64-encoded hashes
+type ContentDigest = BTreeMap<String, String>;
+
+/// Parse a `Content-Digest` header like `sha-256=:<base64>:, sha-384=:<base64>:`
+fn parse_content_digest(header: &str) -> Result<ContentDigest> {
+    header
+        .split(',')
+        .map(|item| {
+            let (alg, hash) = item
+                .trim()
+                .split_once('=')
+                .and_then(|(alg, hash)| Some((alg, hash.strip_prefix(':')?.strip_suffix(':')?)))
+                .with_context(|| format!("invalid Content-Digest header `{header}`"))?;
+            Ok((alg.to_lowercase(), hash.to_string()))
+        })
+        .collect()
+}
+
+/// Verify that `body` matches the hashes of all supported algorithms of `expected`
+fn verify_digest(expected: &ContentDigest, body: &[u8]) -> Result<()> {
+    let mut verified = false;
+    for (alg, hash) in expected {
+        let alg = match alg.as_str() {
+            "sha-256" => &SHA256,
+            "sha-384" => &SHA384,
+            "sha-512" => &SHA512,
+            _ => continue,
+        };
+        let hash = base64::decode(hash).context("invalid base64 in content digest")?;
+        ensure!(
+            digest(alg, body).as_ref() == hash,
+            "content digest mismatch"
+        );
+        verified = true;
+    }
+    ensure!(verified, "content digest has no supported algorithm");
+    Ok(())
+}
+
+/// Read the body of `res`, which must be at most `limit` bytes long and match
+/// `expected`, or else the `Content-Digest` header of the response if it has one.
+fn read_body(res: Response, limit: u64, expected: Option<&ContentDigest>) -> Result<Vec<u8>> {
+    let header = res
+        .header("Content-Digest")
+        .map(parse_content_digest)
+        .transpose()?;
+
+    let mut body = Vec::new();
     res.into_reader()
-        .take(MAX_WASM_SIZE)
-        .read_to_end(&mut wasm)
-        .context("failed to read WASM module contents")?;
-    // TODO: Verify Content-Digest
-    Ok(wasm)
+        .take(limit + 1)
+        .read_to_end(&mut body)
+        .context("failed to read response body")?;
+    ensure!(
+        body.len() as u64 <= limit,
+        "response body exceeds the maximum size of {limit} bytes"
+    );
+
+    if let Some(expected) = expected.or(header.as_ref()) {
+        verify_digest(expected, &body)?;
+    }
+    Ok(body)
 }
 
-fn get_drawbridge_directory(url: impl AsRef<str>) -> Result<(Vec<u8>, String)> {
-    let url = url.as_ref().trim_end_matches('/');
-    let wasm = get_typed(WASM_MEDIA_TYPE, format!("{url}/{PACKAGE_ENTRYPOINT}"))
-        .context(format!("failed to fetch `{PACKAGE_ENTRYPOINT}`"))
-        .and_then(response_into_wasm)?;
+/// An entry of a Drawbridge directory listing
+#[derive(Deserialize)]
+struct DrawbridgeEntry {
+    digest: ContentDigest,
+    #[serde(rename = "type")]
+    typ: String,
+}
+
+type DrawbridgeDirectory = BTreeMap<String, DrawbridgeEntry>;
+
+fn parse_directory(body: &[u8]) -> Result<DrawbridgeDirectory> {
+    serde_json::from_slice(body).context("failed to parse directory listing")
+}
 
-    let conf = get_typed(TOML_MEDIA_TYPE, format!("{url}/{PACKAGE_CONFIG}"))
-        .context(format!("failed to fetch `{PACKAGE_CONFIG}`"))?
-        // TODO: Verify Content-Digest
-        .into_string()?;
+/// Recursively fetch all entries of the Drawbridge directory `dir` located at `url`,
+/// reading at most `remaining` bytes of directory listings and assets.
+fn get_drawbridge_assets(
+    agent: &Agent,
+    url: &str,
+    dir: DrawbridgeDirectory,
+    remaining: &mut u64,
+) -> Result<Directory> {
+    dir.into_iter()
+        .map(|(name, entry)| {
+            let url = format!("{url}/{name}");
+            let is_dir = entry.typ == DRAWBRIDGE_DIRECTORY_MEDIA_TYPE;
+            let res = if is_dir {
+                get_typed(agent, DRAWBRIDGE_DIRECTORY_MEDIA_TYPE, &url)?
+            } else {
+                get(agent, &url)?
+            };
+            let body = read_body(res, *remaining, Some(&entry.digest))
+                .with_context(|| format!("failed to read `{url}`"))?;
+            *remaining -= body.len() as u64;
+
+            let node = if is_dir {
+                let dir = parse_directory(&body)?;
+                Node::Directory(get_drawbridge_assets(agent, &url, dir, remaining)?.into())
+            } else {
+                Node::File(body.into())
+            };
+            Ok((name, node))
+        })
+        .collect()
+}
 
-    Ok((wasm, conf))
+/// Fetch the package at `url`, whose directory listing in `res` must match `digest`
+fn get_drawbridge_directory(
+    agent: &Agent,
+    url: impl AsRef<str>,
+    res: Response,
+    digest: Option<&ContentDigest>,
+) -> Result<(Vec<u8>, String, Directory)> {
+    let url = url.as_ref().trim_end_matches('/');
+    let mut remaining = MAX_ASSETS_SIZE;
+    let body = read_body(res, remaining, digest).context("failed to read package directory")?;
+    remaining -= body.len() as u64;
+    let mut dir = parse_directory(&body)?;
+
+    // The entrypoint and config are verified against the digests of the listing.
+    let mut get_entry = |name: &str, typ: &str, limit: u64| -> Result<Vec<u8>> {
+        let entry = dir
+            .remove(name)
+            .with_context(|| format!("package has no `{name}`"))?;
+        get_typed(agent, typ, format!("{url}/{name}"))
+            .and_then(|res| read_body(res, limit, Some(&entry.digest)))
+            .with_context(|| format!("failed to fetch `{name}`"))
+    };
+    let wasm = get_entry(PACKAGE_ENTRYPOINT, WASM_MEDIA_TYPE, MAX_WASM_SIZE)?;
+    let conf = get_entry(PACKAGE_CONFIG, TOML_MEDIA_TYPE, MAX_CONFIG_SIZE)?;
+    let conf = String::from_utf8(conf).context("package config is not valid UTF-8")?;
+
+    // Everything else in the package is made available to the workload as an asset.
+    let assets = get_drawbridge_assets(agent, url, dir, &mut remaining)
+        .context("failed to fetch package assets")?;
+
+    Ok((wasm, conf, assets))
 }
 
 impl Loader<Requested> {
@@ -162,13 +316,20 @@ impl Loader<Requested> {
 
     pub fn next(mut self) -> Result<Loader<Attested>> {
         // TODO: First acquire the Steward URL and then pull the WASM module after attestation.
-        let (webasm, config) = match self.0.package {
+        let (webasm, config, assets) = match self.0.package {
             Package::Remote(ref url) => {
-                let res = get(url.as_str())?;
+                // A signed package digest binds everything fetched to the signature.
+                let digest = self.0.package_digest.as_ref();
+                let agent = agent(self.0.ca_bundle.as_deref())?;
+                let res = get(&agent, url.as_str())?;
                 match res.content_type() {
-                    WASM_MEDIA_TYPE => response_into_wasm(res).map(|webasm| (webasm, None))?,
-                    DRAWBRIDGE_DIRECTORY_MEDIA_TYPE => get_drawbridge_directory(url.as_str())
-                        .map(|(webasm, config)| (webasm, Some(config)))?,
+                    WASM_MEDIA_TYPE => read_body(res, MAX_WASM_SIZE, digest)
+                        .context("failed to read WASM module contents")
+                        .map(|webasm| (webasm, None, Default::default()))?,
+                    DRAWBRIDGE_DIRECTORY_MEDIA_TYPE => {
+                        get_drawbridge_directory(&agent, url.as_str(), res, digest)
+                            .map(|(webasm, config, assets)| (webasm, Some(config), assets))?
+                    }
                     typ => bail!("unsupported content type: {typ}"),
                 }
             }
@@ -199,7 +360,7 @@ impl Loader<Requested> {
                 } else {
                     None
                 };
-                (webasm, config)
+                (webasm, config, Default::default())
             }
         };
         let config: Config = if let Some(ref config) = config {
@@ -258,6 +419,25 @@ impl Loader<Requested> {
             cltcfg: Arc::new(cltcfg),
             config,
             webasm,
+            assets: assets.into(),
+            insecure_keys: self.0.insecure_keys,
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_digest() {
+        let hash = base64::encode(digest(&SHA256, b"hello"));
+        let expected = parse_content_digest(&format!("sha-256=:{hash}:, unknown=:AA==:")).unwrap();
+        verify_digest(&expected, b"hello").unwrap();
+        assert!(verify_digest(&expected, b"hellO").is_err());
+
+        let unsupported = BTreeMap::from([("unknown".to_string(), "AA==".to_string())]);
+        assert!(verify_digest(&unsupported, b"hello").is_err());
+        assert!(parse_content_digest("sha-256=abc").is_err());
+    }
+}
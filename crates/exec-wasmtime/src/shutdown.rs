@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+//! The socket on which the host signals that the Keep should shut down
+
+use std::os::unix::net::UnixStream;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use wasi_common::WasiFile;
+
+static HOST: Mutex<Option<UnixStream>> = Mutex::new(None);
+
+/// Keep the socket of the host for a `shutdown` file.
+///
+/// The host writes the name of the signal it received, e.g. `SIGTERM`, and
+/// closes its end of the socket, so that the file becomes readable.
+pub fn set_host(host: UnixStream) {
+    *HOST.lock().unwrap() = Some(host);
+}
+
+/// Create the `shutdown` file, which becomes readable when the host asks the
+/// Keep to shut down.
+pub fn file() -> Result<Box<dyn WasiFile>> {
+    let host = HOST
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| anyhow!("shutdown notifications are unavailable or already in use"))?;
+    let host = cap_std::os::unix::net::UnixStream::from_std(host);
+    Ok(wasmtime_wasi::net::Socket::from(host).into())
+}
+
+/// Close the socket of the host, unless a `shutdown` file took it.
+///
+/// The host notices that its end is closed and stops waiting for the workload
+/// to shut down on its own.
+pub fn close_unused() {
+    drop(HOST.lock().unwrap().take());
+}
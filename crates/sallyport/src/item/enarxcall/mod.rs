@@ -13,6 +13,24 @@ use core::mem::size_of;
 #[allow(dead_code)]
 pub const SYS_GETATT: i64 = 0xEA01;
 
+/// `get_key` syscall number used by the shim.
+///
+/// Returns a sealing key of the Keep bound to the policy passed as first
+/// argument, one of [`KEY_POLICY_MEASUREMENT`] or [`KEY_POLICY_SIGNER`].
+/// The key is derived for the security version of the Keep passed as fourth
+/// argument, which must not exceed the current one, or [`KEY_SVN_CURRENT`].
+#[allow(dead_code)]
+pub const SYS_GETKEY: i64 = 0xEA02;
+
+/// `get_key` policy binding the key to the measurement of the Keep
+pub const KEY_POLICY_MEASUREMENT: usize = 0;
+
+/// `get_key` policy binding the key to the signer of the Keep
+pub const KEY_POLICY_SIGNER: usize = 1;
+
+/// `get_key` security version deriving the key for the current one of the Keep
+pub const KEY_SVN_CURRENT: usize = usize::MAX;
+
 /// Payload of an [`Item`](super::Item) of [`Kind::Enarxcall`](super::Kind::Enarxcall).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(C, align(8))]
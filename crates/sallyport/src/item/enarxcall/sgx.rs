@@ -184,6 +184,83 @@ impl TargetInfo {
     }
 }
 
+/// Description of the key to derive with EGETKEY.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, align(512))]
+pub struct KeyRequest {
+    pub keyname: u16,
+    pub keypolicy: u16,
+    pub isvsvn: u16,
+    reserved1: [u8; 2],
+    pub cpusvn: [u64; 2],
+    pub attributemask: [u64; 2],
+    pub keyid: [u8; 32],
+    pub miscmask: u32,
+    pub configsvn: u16,
+    reserved2: [u8; 434],
+}
+
+impl Default for KeyRequest {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            keyname: 0,
+            keypolicy: 0,
+            isvsvn: 0,
+            reserved1: [0; 2],
+            cpusvn: [0, 0],
+            attributemask: [0, 0],
+            keyid: [0; 32],
+            miscmask: 0,
+            configsvn: 0,
+            reserved2: [0; 434],
+        }
+    }
+}
+
+/// A key derived with EGETKEY
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct Key(pub [u8; 16]);
+
+impl KeyRequest {
+    /// `keyname` of the seal key
+    pub const SEAL_KEY: u16 = 4;
+
+    /// `keypolicy` deriving the key from MRENCLAVE
+    pub const POLICY_MRENCLAVE: u16 = 1;
+
+    /// `keypolicy` deriving the key from MRSIGNER
+    pub const POLICY_MRSIGNER: u16 = 1 << 1;
+
+    /// Derive the requested key, returning the EGETKEY error code on failure.
+    #[inline]
+    pub fn enclu_egetkey(&self) -> Result<Key, u64> {
+        const EGETKEY: usize = 1;
+
+        let mut key = Key([0; 16]);
+        let ret: u64;
+
+        // See `TargetInfo::enclu_ereport()` for the handling of rbx.
+        unsafe {
+            asm!(
+                "xchg       {RBX}, rbx",
+                "enclu",
+                "mov        rbx, {RBX}",
+
+                RBX = inout(reg) self => _,
+                inout("rax") EGETKEY => ret,
+                in("rcx") &mut key as *mut Key,
+            );
+        }
+
+        match ret {
+            0 => Ok(key),
+            e => Err(e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,5 +306,18 @@ mod tests {
             configid: 64,
             reserved3: 128
         }
+
+        struct KeyRequest: 512, 512 => {
+            keyname: 0,
+            keypolicy: 2,
+            isvsvn: 4,
+            reserved1: 6,
+            cpusvn: 8,
+            attributemask: 24,
+            keyid: 40,
+            miscmask: 72,
+            configsvn: 76,
+            reserved2: 78
+        }
     }
 }
@@ -340,7 +340,10 @@ impl EnarxAllocator {
 
         let (first_half, first_half_size) = {
             while self.allocator.free() < curr_size {
-                self.balloon();
+                // The host may refuse to grow the Keep beyond its memory limit.
+                if !self.balloon() {
+                    return Err(AllocateError::OutOfMemory);
+                }
             }
             let (chunk, chunk_size) = self.try_alloc_half(curr_size);
 
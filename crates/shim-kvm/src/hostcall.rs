@@ -8,23 +8,24 @@ use crate::eprintln;
 use crate::exec::{BRK_LINE, NEXT_MMAP_RWLOCK};
 use crate::paging::SHIM_PAGETABLE;
 use crate::snp::attestation::{asn1_encode_report_vcek, SnpReportResponseData};
-use crate::snp::ghcb::{GHCB, GHCB_EXT, SNP_ATTESTATION_LEN_MAX};
+use crate::snp::ghcb::{GHCB, GHCB_EXT, SNP_ATTESTATION_LEN_MAX, SNP_KEY_LEN};
 use crate::snp::snp_active;
 use crate::spin::{RacyCell, RwLocked};
 
 use const_default::ConstDefault;
 use core::ffi::{c_int, c_size_t, c_ulong, c_void};
 use core::mem::size_of;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range};
 use core::ptr::NonNull;
 use core::slice;
 
 use sallyport::guest::{self, Handler, Platform, ThreadLocalStorage};
 use sallyport::item::enarxcall::sev::TECH;
+use sallyport::item::enarxcall::{KEY_POLICY_MEASUREMENT, KEY_POLICY_SIGNER, KEY_SVN_CURRENT};
 use sallyport::item::syscall;
 use sallyport::libc::{
-    off_t, EAGAIN, EFAULT, EINVAL, EIO, EMSGSIZE, ENOMEM, MAP_ANONYMOUS, MAP_PRIVATE, PROT_EXEC,
-    PROT_WRITE,
+    off_t, EAGAIN, EFAULT, EINVAL, EIO, EMSGSIZE, ENOMEM, ENOSYS, ENOTSUP, MAP_ANONYMOUS,
+    MAP_PRIVATE, PROT_EXEC, PROT_WRITE,
 };
 use sallyport::util::ptr::is_aligned_non_null;
 use sallyport::{libc, KVM_SYSCALL_TRIGGER_PORT};
@@ -244,6 +245,88 @@ impl<'a> HostCall<'a> {
 
         Ok([len, TECH])
     }
+
+    /// get an SNP sealing key for `policy` and the guest SVN `svn`
+    ///
+    /// Plain KVM has no key to offer and returns `ENOSYS`. Signer keys are
+    /// refused with `ENOTSUP`, as the family and image IDs alone are chosen by
+    /// the host and do not identify the signer of the Keep.
+    pub fn get_key(
+        &mut self,
+        platform: &impl Platform,
+        policy: usize,
+        buf: usize,
+        buf_len: usize,
+        svn: usize,
+    ) -> Result<[usize; 2], c_int> {
+        // Guest fields mixed into the key by `MSG_KEY_REQ`
+        const GUEST_POLICY: u64 = 1;
+        const MEASUREMENT: u64 = 1 << 3;
+        const GUEST_SVN: u64 = 1 << 4;
+        const TCB_VERSION: u64 = 1 << 5;
+
+        // Guest SVN and reported TCB in the attestation report
+        const REPORT_GUEST_SVN: Range<usize> = 0x4..0x8;
+        const REPORT_TCB: Range<usize> = 0x180..0x188;
+
+        if !snp_active() {
+            return Err(ENOSYS);
+        }
+
+        let guest_field_select = match policy {
+            KEY_POLICY_MEASUREMENT => GUEST_POLICY | MEASUREMENT,
+            KEY_POLICY_SIGNER => return Err(ENOTSUP),
+            _ => return Err(EINVAL),
+        };
+
+        if buf == 0 {
+            return Ok([SNP_KEY_LEN, TECH]);
+        }
+
+        if buf_len < SNP_KEY_LEN {
+            return Err(EMSGSIZE);
+        }
+
+        let user_buf = platform.validate_slice_mut::<u8>(buf, SNP_KEY_LEN)?;
+
+        // Bind the key to the current TCB version of the platform and to the
+        // requested SVN of the guest, like the SGX shim does with the CPU SVN
+        // and the ISV SVN. The current ones are taken from a report.
+        let mut report_buf = [0u8; SNP_ATTESTATION_LEN_MAX];
+        let len = GHCB_EXT
+            .get_report(1, &[0; 64], &mut report_buf)
+            .map_err(|_| EIO)?;
+        let header = size_of::<SnpReportResponseData>();
+        let report_ptr = report_buf.as_ptr() as *const SnpReportResponseData;
+        let response = unsafe { report_ptr.read_unaligned() };
+        if len < header || response.status != 0 {
+            return Err(EIO);
+        }
+        let report = report_buf
+            .get(header..)
+            .and_then(|r| r.get(..response.size as usize))
+            .ok_or(EIO)?;
+
+        let mut current_svn = [0u8; 4];
+        current_svn.copy_from_slice(report.get(REPORT_GUEST_SVN).ok_or(EIO)?);
+        let current_svn = u32::from_le_bytes(current_svn);
+        let mut tcb = [0u8; 8];
+        tcb.copy_from_slice(report.get(REPORT_TCB).ok_or(EIO)?);
+        let tcb = u64::from_le_bytes(tcb);
+
+        let guest_svn = match svn {
+            KEY_SVN_CURRENT => current_svn,
+            svn if svn <= current_svn as usize => svn as u32,
+            _ => return Err(EINVAL),
+        };
+
+        let key = GHCB_EXT
+            .get_key(guest_field_select | GUEST_SVN | TCB_VERSION, guest_svn, tcb)
+            .map_err(|_| EIO)?;
+        user_buf.copy_from_slice(&key);
+
+        Ok([SNP_KEY_LEN, TECH])
+    }
 }
 
 impl Handler for HostCall<'_> {
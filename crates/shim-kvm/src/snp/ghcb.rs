@@ -19,7 +19,7 @@ use aes_gcm::AeadInPlace;
 use aes_gcm::NewAead;
 use aes_gcm::{Aes256Gcm, Key, Nonce, Tag};
 use const_default::ConstDefault;
-use sallyport::libc::EINVAL;
+use sallyport::libc::{EINVAL, EIO};
 use spinning::Lazy;
 use x86_64::registers::model_specific::Msr;
 use x86_64::structures::paging::{Page, Size4KiB};
@@ -50,6 +50,15 @@ const SNP_GUEST_MSG_PAYLOAD_LEN: usize = 4000;
 /// Maximum length of an attestation report
 pub const SNP_ATTESTATION_LEN_MAX: usize = SNP_GUEST_MSG_PAYLOAD_LEN;
 
+/// Length of a key derived with `MSG_KEY_REQ`
+pub const SNP_KEY_LEN: usize = 32;
+
+/// Length of a `MSG_KEY_REQ` request
+const SNP_KEY_REQ_LEN: usize = 32;
+
+/// Length of a `MSG_KEY_RSP` response
+const SNP_KEY_RSP_LEN: usize = 64;
+
 #[derive(Copy, Clone, PartialEq)]
 #[repr(u8)]
 #[non_exhaustive]
@@ -58,9 +67,9 @@ enum SnpMsgType {
        TypeInvalid = 0,
        CpuidReq,
        CpuidRsp,
-       KeyReq,
-       KeyRsp,
     */
+    KeyReq = 3,
+    KeyRsp = 4,
     ReportReq = 5,
     ReportRsp = 6,
     /*
@@ -642,9 +651,10 @@ impl GhcbExtHandle {
         // prevent later reads from being moved before this point
         core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
 
-        if ret.is_ok() {
-            SECRETS.inc_msg_seqno_0();
-        }
+        // Consume the sequence number even if the request failed, as the
+        // host has seen the message encrypted with it. Reusing it for the
+        // next message would reuse the AES-GCM nonce.
+        SECRETS.inc_msg_seqno_0();
 
         ret
     }
@@ -783,13 +793,55 @@ impl Locked<&mut GhcbExtHandle> {
         this.enc_payload(version, SnpMsgType::ReportReq, &mut user_data)
             .expect("encryption failed");
 
-        unsafe { this.guest_req().expect("request failed") };
+        unsafe { this.guest_req() }.map_err(|_| EIO as u64)?;
 
         this.dec_payload(response, SnpMsgType::ReportRsp)
-            .expect("decryption failed");
+            .map_err(|_| EIO as u64)?;
 
         Ok(this.response.hdr.msg_sz as _)
     }
+
+    /// Derive a key from the VCEK via the GHCB shared page protocol
+    ///
+    /// `guest_field_select` selects the guest fields mixed into the key, e.g.
+    /// the measurement or the family and image IDs. `guest_svn` and
+    /// `tcb_version` are mixed in if selected, and must not exceed the current
+    /// ones.
+    pub fn get_key(
+        &self,
+        guest_field_select: u64,
+        guest_svn: u32,
+        tcb_version: u64,
+    ) -> Result<[u8; SNP_KEY_LEN], u64> {
+        let mut this = self.lock();
+
+        // The root key is the VCEK and the key is derived for VMPL0.
+        let mut request = [0u8; SNP_KEY_REQ_LEN];
+        request[8..16].copy_from_slice(&guest_field_select.to_le_bytes());
+        request[20..24].copy_from_slice(&guest_svn.to_le_bytes());
+        request[24..32].copy_from_slice(&tcb_version.to_le_bytes());
+
+        this.request = <SnpGuestMsg as ConstDefault>::DEFAULT;
+
+        this.enc_payload(1, SnpMsgType::KeyReq, &mut request)
+            .expect("encryption failed");
+
+        unsafe { this.guest_req() }.map_err(|_| EIO as u64)?;
+
+        let mut response = [0u8; SNP_KEY_RSP_LEN];
+        this.dec_payload(&mut response, SnpMsgType::KeyRsp)
+            .map_err(|_| EIO as u64)?;
+
+        let mut status = [0u8; 4];
+        status.copy_from_slice(&response[..4]);
+        if this.response.hdr.msg_sz as usize != SNP_KEY_RSP_LEN || u32::from_le_bytes(status) != 0 {
+            return Err(EIO as _);
+        }
+
+        let mut key = [0u8; SNP_KEY_LEN];
+        key.copy_from_slice(&response[32..]);
+        Ok(key)
+    }
 }
 
 #[cfg(test)]
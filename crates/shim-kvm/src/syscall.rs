@@ -10,7 +10,7 @@ use core::mem::size_of;
 
 use sallyport::guest;
 use sallyport::guest::Handler;
-use sallyport::item::enarxcall::SYS_GETATT;
+use sallyport::item::enarxcall::{SYS_GETATT, SYS_GETKEY};
 #[cfg(feature = "dbg")]
 use sallyport::libc::{SYS_write, STDERR_FILENO, STDOUT_FILENO};
 use spinning::Lazy;
@@ -135,8 +135,11 @@ extern "sysv64" fn syscall_rust(
     let usermemscope = UserMemScope;
 
     match nr as i64 {
-        SYS_GETATT => {
-            let ret = h.get_attestation(&usermemscope, a, b, c, d);
+        SYS_GETATT | SYS_GETKEY => {
+            let ret = match nr as i64 {
+                SYS_GETATT => h.get_attestation(&usermemscope, a, b, c, d),
+                _ => h.get_key(&usermemscope, a, b, c, d),
+            };
 
             match ret {
                 Err(e) => {
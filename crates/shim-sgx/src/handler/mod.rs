@@ -42,8 +42,10 @@ use mmledger::Access;
 use primordial::{Address, Offset, Page};
 use sallyport::guest::Handler as _;
 use sallyport::guest::{self, Platform, ThreadLocalStorage};
-use sallyport::item::enarxcall::sgx::{Report, ReportData, TargetInfo, TECH};
-use sallyport::item::enarxcall::SYS_GETATT;
+use sallyport::item::enarxcall::sgx::{KeyRequest, Report, ReportData, TargetInfo, TECH};
+use sallyport::item::enarxcall::{
+    KEY_POLICY_MEASUREMENT, KEY_POLICY_SIGNER, KEY_SVN_CURRENT, SYS_GETATT, SYS_GETKEY,
+};
 use sallyport::libc::{
     off_t, EACCES, EINVAL, EMSGSIZE, ENOMEM, ENOSYS, ENOTSUP, MAP_ANONYMOUS, MAP_PRIVATE,
     PROT_EXEC, PROT_READ, PROT_WRITE, STDERR_FILENO,
@@ -131,24 +133,32 @@ impl<'a> Handler<'a> {
             return Err(ENOMEM);
         }
 
+        // The host restricts the pages with ENCLS[EMODPR], which leaves each
+        // of them with the intersection of its current and the requested
+        // permissions.
         self.mprotect_host(addr_in, length.bytes(), prot)?;
 
+        let flags = Flags::from_bits_truncate(prot as u8);
         for i in 0..pages {
-            let virt_addr = VirtAddr::new((addr.raw() + i * Page::SIZE) as u64);
+            let page = Address::new(addr.raw() + i * Page::SIZE);
+            let current = heap.access(page).unwrap_or_else(|| self.attacked());
+            let restricted = Flags::from_bits_truncate(current.bits() as u8) & flags;
+
+            let virt_addr = VirtAddr::new(page.raw() as u64);
             // Safety: The address is guaranteed to be page aligned, because
             // `addr` was checked to be page aligned and only a multiple of
             // pages was added.
             let page_addr = unsafe { PageAddr::from_start_address_unchecked(virt_addr) };
 
-            // TODO: https://github.com/enarx/enarx/issues/1892
             Class::Regular
-                .info(Flags::READ | Flags::RESTRICTED)
+                .info(restricted | Flags::RESTRICTED)
                 .accept(page_addr)
                 .unwrap_or_else(|_| self.attacked());
 
-            Class::Regular
-                .info(Flags::from_bits_truncate(prot as u8))
-                .extend(page_addr);
+            // Permissions added by the request are extended with ENCLU[EMODPE].
+            if restricted != flags {
+                Class::Regular.info(flags).extend(page_addr);
+            }
         }
 
         let access = Access::from_bits_truncate(prot as usize);
@@ -485,6 +495,56 @@ impl<'a> Handler<'a> {
         Ok([len, TECH])
     }
 
+    fn get_key(
+        &mut self,
+        platform: &impl Platform,
+        policy: usize,
+        buf: usize,
+        buf_len: usize,
+        svn: usize,
+    ) -> Result<[usize; 2], c_int> {
+        const KEY_LEN: usize = 16;
+
+        let keypolicy = match policy {
+            KEY_POLICY_MEASUREMENT => KeyRequest::POLICY_MRENCLAVE,
+            KEY_POLICY_SIGNER => KeyRequest::POLICY_MRSIGNER,
+            _ => return Err(EINVAL),
+        };
+
+        if buf == 0 {
+            return Ok([KEY_LEN, TECH]);
+        }
+
+        if buf_len < KEY_LEN {
+            return Err(EMSGSIZE);
+        }
+
+        let buf = platform.validate_slice_mut::<u8>(buf, KEY_LEN)?;
+
+        // Bind the key to the current SVN of the CPU and to the requested SVN
+        // of the enclave, so that versions with known vulnerabilities cannot
+        // derive the keys of later ones.
+        let report = TargetInfo::default().enclu_ereport(&ReportData::default());
+        let isvsvn = match svn {
+            KEY_SVN_CURRENT => report.payload.isvsvn,
+            svn if svn <= report.payload.isvsvn as usize => svn as u16,
+            _ => return Err(EINVAL),
+        };
+        let request = KeyRequest {
+            keyname: KeyRequest::SEAL_KEY,
+            keypolicy,
+            isvsvn,
+            cpusvn: report.payload.cpusvn,
+            attributemask: [u64::MAX; 2],
+            miscmask: u32::MAX,
+            ..Default::default()
+        };
+        let key = request.enclu_egetkey().map_err(|_| EINVAL)?;
+        buf.copy_from_slice(&key.0);
+
+        Ok([KEY_LEN, TECH])
+    }
+
     fn handle_syscall(&mut self) {
         debug!(self, "syscall {} ", self.ssa.gpr.rax as usize);
 
@@ -494,14 +554,23 @@ impl<'a> Handler<'a> {
         let usermemscope = UserMemScope;
 
         match nr as i64 {
-            SYS_GETATT => {
-                let ret = self.get_attestation(
-                    &usermemscope,
-                    self.ssa.gpr.rdi as _,
-                    self.ssa.gpr.rsi as _,
-                    self.ssa.gpr.rdx as _,
-                    self.ssa.gpr.r10 as _,
-                );
+            SYS_GETATT | SYS_GETKEY => {
+                let ret = match nr as i64 {
+                    SYS_GETATT => self.get_attestation(
+                        &usermemscope,
+                        self.ssa.gpr.rdi as _,
+                        self.ssa.gpr.rsi as _,
+                        self.ssa.gpr.rdx as _,
+                        self.ssa.gpr.r10 as _,
+                    ),
+                    _ => self.get_key(
+                        &usermemscope,
+                        self.ssa.gpr.rdi as _,
+                        self.ssa.gpr.rsi as _,
+                        self.ssa.gpr.rdx as _,
+                        self.ssa.gpr.r10 as _,
+                    ),
+                };
                 match ret {
                     Err(e) => self.ssa.gpr.rax = -e as u64,
                     Ok([rax, rdx]) => {
@@ -37,6 +37,11 @@ impl Heap {
         self.ledger.contains(addr, length)
     }
 
+    /// Return the access of the page at `addr`, if it is mapped.
+    pub fn access(&self, addr: Address<usize, Page>) -> Option<Access> {
+        self.ledger.contains(addr, Offset::from_items(1))
+    }
+
     /// Return the maximum `brk` address reached.
     pub fn brk_max(&self) -> Address<usize, Page> {
         self.brk_max
@@ -151,6 +156,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mprotect_access() {
+        let mut heap = Heap::new(Address::new(0), Address::new(BYTES));
+        let addr = heap
+            .mmap(None, Offset::from_items(4), Access::READ | Access::WRITE)
+            .unwrap();
+        let page = |i: usize| Address::new(addr.raw() + i * Page::SIZE);
+
+        // Change the access of the second page only, as `mprotect()` does.
+        heap.mmap(Some(page(1)), Offset::from_items(1), Access::READ)
+            .unwrap();
+        assert_eq!(heap.access(page(0)), Some(Access::READ | Access::WRITE));
+        assert_eq!(heap.access(page(1)), Some(Access::READ));
+        assert_eq!(heap.access(page(2)), Some(Access::READ | Access::WRITE));
+
+        heap.munmap(addr, Offset::from_items(4)).unwrap();
+        assert_eq!(heap.access(page(1)), None);
+    }
+
     #[test]
     fn mmap_oversubscribe() {
         let mut heap = Heap::new(Address::new(0), Address::new(BYTES));
@@ -0,0 +1,384 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verification of the attestation evidence of Enarx Keeps
+//!
+#![doc = include_str!("../README.md")]
+#![deny(missing_docs)]
+#![deny(clippy::all)]
+#![warn(rust_2018_idioms)]
+
+mod pki;
+pub mod policy;
+pub mod sgx;
+pub mod snp;
+
+pub use policy::Policy;
+
+use std::fmt::{self, Formatter};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use const_oid::{AssociatedOid, ObjectIdentifier};
+use der::{Decode, Encode};
+use ring::digest::{self, SHA256, SHA384, SHA512};
+use x509_cert::request::{CertReq, ExtensionReq};
+use x509_cert::Certificate;
+
+/// Technology of a Keep
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Technology {
+    /// KVM, without hardware attestation
+    Kvm,
+    /// AMD SEV-SNP
+    Snp,
+    /// Intel SGX
+    Sgx,
+}
+
+impl Technology {
+    const KVM: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.1");
+    const SGX: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.2");
+    const SNP: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.58270.1.3");
+
+    fn from_oid(oid: ObjectIdentifier) -> Option<Self> {
+        match oid {
+            Self::KVM => Some(Self::Kvm),
+            Self::SGX => Some(Self::Sgx),
+            Self::SNP => Some(Self::Snp),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Technology {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Kvm => "kvm",
+            Self::Snp => "snp",
+            Self::Sgx => "sgx",
+        })
+    }
+}
+
+/// The verified claims of a Keep
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Claims {
+    /// Claims of an SNP Keep
+    Snp(snp::Report),
+    /// Claims of an SGX Keep
+    Sgx(sgx::Report),
+}
+
+impl Claims {
+    /// The technology of the Keep
+    pub fn technology(&self) -> Technology {
+        match self {
+            Self::Snp(..) => Technology::Snp,
+            Self::Sgx(..) => Technology::Sgx,
+        }
+    }
+}
+
+impl fmt::Display for Claims {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Technology: {}", self.technology())?;
+        match self {
+            Self::Snp(report) => write!(f, "{report}"),
+            Self::Sgx(report) => write!(f, "{report}"),
+        }
+    }
+}
+
+/// Copy `N` bytes at `offset` out of `bytes`
+///
+/// Panics if `bytes` is too short, so the length must be checked first.
+fn array<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+    bytes[offset..][..N].try_into().unwrap()
+}
+
+/// Encode bytes in lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The public key and evidence of a certificate or certification request
+struct Request {
+    public_key: Vec<u8>,
+    technology: Technology,
+    evidence: Vec<u8>,
+}
+
+impl Request {
+    fn decode(der: &[u8]) -> Result<Self> {
+        let (public_key, extensions) = if let Ok(cert) = Certificate::from_der(der) {
+            let tbs = cert.tbs_certificate;
+            let extensions = tbs
+                .extensions
+                .iter()
+                .flatten()
+                .map(|e| (e.extn_id, e.extn_value.to_vec()))
+                .collect::<Vec<_>>();
+            (tbs.subject_public_key_info.to_vec()?, extensions)
+        } else {
+            let req = CertReq::from_der(der)
+                .context("Failed to decode the certificate or certification request")?;
+
+            // The certification request must be signed with its own key.
+            pki::verify(
+                &req.info.public_key,
+                &req.algorithm,
+                &req.info.to_vec()?,
+                req.signature.raw_bytes(),
+            )
+            .context("Failed to verify the signature of the certification request")?;
+
+            let mut extensions = vec![];
+            for attr in req.info.attributes.iter() {
+                if attr.oid != ExtensionReq::OID {
+                    continue;
+                }
+                for value in attr.values.iter() {
+                    let der = value.to_vec()?;
+                    let req = ExtensionReq::from_der(&der)
+                        .context("Failed to decode the requested extensions")?;
+                    extensions.extend(req.0.iter().map(|e| (e.extn_id, e.extn_value.to_vec())));
+                }
+            }
+            (req.info.public_key.to_vec()?, extensions)
+        };
+
+        let (technology, evidence) = extensions
+            .into_iter()
+            .find_map(|(oid, value)| Some((Technology::from_oid(oid)?, value)))
+            .context("Missing attestation evidence")?;
+
+        Ok(Self {
+            public_key,
+            technology,
+            evidence,
+        })
+    }
+
+    /// Check that `report_data` is the hash of the public key, zero padded
+    fn check_report_data(
+        &self,
+        report_data: &[u8; 64],
+        algorithm: &'static digest::Algorithm,
+    ) -> Result<()> {
+        let hash = digest::digest(algorithm, &self.public_key);
+        let mut expected = [0u8; 64];
+        expected[..hash.as_ref().len()].copy_from_slice(hash.as_ref());
+
+        if *report_data != expected {
+            bail!("Report data does not match the hash of the public key");
+        }
+        Ok(())
+    }
+}
+
+/// The domain separation tag of the report data of workload evidence
+const WORKLOAD_TAG: &[u8] = b"enarx workload attestation\0";
+
+/// Compute the report data of evidence a workload requested over `data`
+///
+/// Workloads get evidence over `SHA-512("enarx workload attestation\0" || data)`
+/// rather than over `data` itself, which keeps it apart from the evidence of
+/// the Keep's certification request. Compare the result with the report data
+/// of the verified report or quote, e.g. `snp::verify(..)?.report_data`.
+pub fn workload_report_data(data: &[u8]) -> [u8; 64] {
+    let mut context = digest::Context::new(&SHA512);
+    context.update(WORKLOAD_TAG);
+    context.update(data);
+
+    let mut report_data = [0u8; 64];
+    report_data.copy_from_slice(context.finish().as_ref());
+    report_data
+}
+
+/// Verify the evidence of a certificate or certification request
+///
+/// `der` is the DER encoding of an X.509 certificate or PKCS#10 certification
+/// request of a Keep. The signature of a certification request is verified,
+/// the one of a certificate is not, as its issuer is not known here.
+///
+/// `chain` contains the DER encoded certificates the evidence is verified
+/// against, ending with the trust anchor. These are the ASK and the ARK for
+/// SNP, and the PCK platform or processor CA and the root CA for SGX.
+///
+/// The report data of the evidence must be the hash of the public key, and
+/// the claims must satisfy `policy`.
+pub fn verify(der: &[u8], chain: &[Vec<u8>], policy: &Policy) -> Result<Claims> {
+    let now = SystemTime::now();
+    let request = Request::decode(der)?;
+    let chain = chain
+        .iter()
+        .map(|der| Certificate::from_der(der))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to decode the certificate chain")?;
+
+    let claims = match request.technology {
+        Technology::Kvm => bail!("Keeps of the kvm technology have no verifiable evidence"),
+
+        Technology::Snp => {
+            let report = snp::verify(&request.evidence, &chain, now)?;
+            request.check_report_data(&report.report_data, &SHA384)?;
+            policy.snp.check(&report)?;
+            Claims::Snp(report)
+        }
+
+        Technology::Sgx => {
+            let report = sgx::verify(&request.evidence, &chain, now)?;
+            request.check_report_data(&report.report_data, &SHA256)?;
+            policy.sgx.check(&report)?;
+            Claims::Sgx(report)
+        }
+    };
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNP_CSR: &str = include_str!("../testdata/snp.csr");
+    const SNP_TCB_CSR: &str = include_str!("../testdata/snp-tcb.csr");
+    const SNP_CHAIN: &[u8] = include_bytes!("../testdata/snp-chain.pem");
+    const SGX_CSR: &str = include_str!("../testdata/sgx.csr");
+    const SGX_CHAIN: &[u8] = include_bytes!("../testdata/sgx-chain.pem");
+
+    fn load_csr(pem: &str) -> Vec<u8> {
+        let (label, doc) = der::Document::from_pem(pem).unwrap();
+        assert_eq!(label, "CERTIFICATE REQUEST");
+        doc.as_bytes().to_vec()
+    }
+
+    fn load_chain(pem: &[u8]) -> Vec<Vec<u8>> {
+        rustls_pemfile::certs(&mut &pem[..]).unwrap()
+    }
+
+    fn certs(chain: &[Vec<u8>]) -> Vec<Certificate<'_>> {
+        chain
+            .iter()
+            .map(|der| Certificate::from_der(der).unwrap())
+            .collect()
+    }
+
+    fn policy(toml: &str) -> Policy {
+        toml.parse().unwrap()
+    }
+
+    #[test]
+    fn snp() {
+        let (csr, chain) = (load_csr(SNP_CSR), load_chain(SNP_CHAIN));
+
+        let report = match verify(&csr, &chain, &Policy::default()).unwrap() {
+            Claims::Snp(report) => report,
+            claims => panic!("unexpected claims: {claims:?}"),
+        };
+        assert_eq!(report.guest_svn, 1);
+        assert_eq!(report.policy, 0x30000);
+        assert_eq!(
+            report.measurement[..].to_vec(),
+            (0x40..0x70).collect::<Vec<u8>>()
+        );
+        assert_eq!(
+            report.reported_tcb,
+            snp::Tcb {
+                bootloader: 3,
+                tee: 0,
+                snp: 8,
+                microcode: 115
+            }
+        );
+
+        let measurement = hex(&report.measurement);
+        verify(&csr, &chain, &policy(&format!(
+            "[snp]\nmeasurement = \"{measurement}\"\nmin-guest-svn = 1\nmin-tcb = {{ bootloader = 3, tee = 0, snp = 8, microcode = 115 }}"
+        )))
+        .unwrap();
+
+        for toml in [
+            "[snp]\nmeasurement = \"00\"",
+            "[snp]\nmin-guest-svn = 2",
+            "[snp]\nmin-tcb = { bootloader = 0, tee = 0, snp = 0, microcode = 116 }",
+        ] {
+            assert!(verify(&csr, &chain, &policy(toml)).is_err(), "{toml}");
+        }
+
+        assert!(verify(&csr, &load_chain(SGX_CHAIN), &Policy::default()).is_err());
+        assert!(verify(&csr, &chain[..1], &Policy::default()).is_err());
+    }
+
+    #[test]
+    fn workload() {
+        let report_data = workload_report_data(b"nonce");
+        assert_eq!(
+            hex(&report_data),
+            "87b9e854d11dc9d4081876e820f99756c9fdd2d7a5b9c31a9d81dc7ac0ea63e3\
+             c336f5c5ca528b72c023bb72ee6d18cf60de7d998a45c0713721b03e1322aad7"
+        );
+    }
+
+    #[test]
+    fn snp_tampered() {
+        let chain = load_chain(SNP_CHAIN);
+        let mut evidence = Request::decode(&load_csr(SNP_CSR)).unwrap().evidence;
+        snp::verify(&evidence, &certs(&chain), SystemTime::now()).unwrap();
+
+        // Flip a bit of the measurement at the end of the evidence.
+        let measurement = evidence.len() - 0x4A0 + 0x90;
+        evidence[measurement] ^= 1;
+        assert!(snp::verify(&evidence, &certs(&chain), SystemTime::now()).is_err());
+
+        // A correctly signed report whose TCB differs from the one of the VCEK
+        let csr = load_csr(SNP_TCB_CSR);
+        let evidence = Request::decode(&csr).unwrap().evidence;
+        let err = snp::verify(&evidence, &certs(&chain), SystemTime::now()).unwrap_err();
+        assert!(err.to_string().contains("TCB"), "{err}");
+        assert!(verify(&csr, &chain, &Policy::default()).is_err());
+    }
+
+    #[test]
+    fn sgx() {
+        let (csr, chain) = (load_csr(SGX_CSR), load_chain(SGX_CHAIN));
+
+        let report = match verify(&csr, &chain, &Policy::default()).unwrap() {
+            Claims::Sgx(report) => report,
+            claims => panic!("unexpected claims: {claims:?}"),
+        };
+        assert_eq!(report.mrenclave[..].to_vec(), (0..32).collect::<Vec<u8>>());
+        assert_eq!(report.mrsigner[..].to_vec(), (32..64).collect::<Vec<u8>>());
+        assert_eq!(report.isv_prod_id, 0xea00);
+        assert_eq!(report.isv_svn, 1);
+        assert!(!report.debug());
+
+        let (mrenclave, mrsigner) = (hex(&report.mrenclave), hex(&report.mrsigner));
+        verify(&csr, &chain, &policy(&format!(
+            "[sgx]\nmrenclave = \"{mrenclave}\"\nmrsigner = \"{mrsigner}\"\nisv-prod-id = 59904\nmin-isv-svn = 1"
+        )))
+        .unwrap();
+
+        for toml in [
+            "[sgx]\nmrenclave = \"00\"",
+            "[sgx]\nmrsigner = \"00\"",
+            "[sgx]\nisv-prod-id = 1",
+            "[sgx]\nmin-isv-svn = 2",
+        ] {
+            assert!(verify(&csr, &chain, &policy(toml)).is_err(), "{toml}");
+        }
+
+        assert!(verify(&csr, &load_chain(SNP_CHAIN), &Policy::default()).is_err());
+    }
+
+    #[test]
+    fn sgx_tampered() {
+        let chain = load_chain(SGX_CHAIN);
+        let mut evidence = Request::decode(&load_csr(SGX_CSR)).unwrap().evidence;
+        sgx::verify(&evidence, &certs(&chain), SystemTime::now()).unwrap();
+
+        // Flip a bit of MRENCLAVE in the enclave report after the header.
+        evidence[48 + 64] ^= 1;
+        assert!(sgx::verify(&evidence, &certs(&chain), SystemTime::now()).is_err());
+    }
+}
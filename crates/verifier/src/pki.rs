@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Certificate chains and signatures
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use const_oid::db::rfc5912::{
+    ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, ID_EC_PUBLIC_KEY, ID_RSASSA_PSS, RSA_ENCRYPTION,
+    SECP_256_R_1, SECP_384_R_1, SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION,
+};
+use der::Encode;
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+use spki::{AlgorithmIdentifier, SubjectPublicKeyInfo};
+use x509_cert::Certificate;
+
+/// Find the ring algorithm verifying `algorithm` signatures made by `key`
+fn verification(
+    key: &SubjectPublicKeyInfo<'_>,
+    algorithm: &AlgorithmIdentifier<'_>,
+) -> Result<&'static dyn VerificationAlgorithm> {
+    let curve = || key.algorithm.parameters_oid().ok();
+
+    Ok(match (key.algorithm.oid, algorithm.oid) {
+        (ID_EC_PUBLIC_KEY, ECDSA_WITH_SHA_256) => match curve() {
+            Some(SECP_256_R_1) => &signature::ECDSA_P256_SHA256_ASN1,
+            Some(SECP_384_R_1) => &signature::ECDSA_P384_SHA256_ASN1,
+            _ => bail!("Unsupported elliptic curve, expected P-256 or P-384"),
+        },
+        (ID_EC_PUBLIC_KEY, ECDSA_WITH_SHA_384) => match curve() {
+            Some(SECP_256_R_1) => &signature::ECDSA_P256_SHA384_ASN1,
+            Some(SECP_384_R_1) => &signature::ECDSA_P384_SHA384_ASN1,
+            _ => bail!("Unsupported elliptic curve, expected P-256 or P-384"),
+        },
+        // AMD signs with SHA-384, MGF1 with SHA-384 and a salt of 48 bytes,
+        // which is the only parametrization of RSASSA-PSS supported.
+        (RSA_ENCRYPTION, ID_RSASSA_PSS) => &signature::RSA_PSS_2048_8192_SHA384,
+        (RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION) => &signature::RSA_PKCS1_2048_8192_SHA256,
+        (RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION) => &signature::RSA_PKCS1_2048_8192_SHA384,
+        (key, algorithm) => bail!("Unsupported signature algorithm {algorithm} for a {key} key"),
+    })
+}
+
+/// Verify the X.509 encoded `sig` over `msg` with `key`
+pub fn verify(
+    key: &SubjectPublicKeyInfo<'_>,
+    algorithm: &AlgorithmIdentifier<'_>,
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<()> {
+    UnparsedPublicKey::new(verification(key, algorithm)?, key.subject_public_key)
+        .verify(msg, sig)
+        .map_err(|_| anyhow!("Invalid signature"))
+}
+
+/// Verify that `cert` is issued and signed by `issuer`
+pub fn verify_signed(cert: &Certificate<'_>, issuer: &Certificate<'_>) -> Result<()> {
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        bail!("Certificate issuer does not match the subject of the issuing certificate");
+    }
+
+    verify(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &cert.signature_algorithm,
+        &cert.tbs_certificate.to_vec()?,
+        cert.signature.raw_bytes(),
+    )
+}
+
+/// Verify that `cert` is valid at `now`
+fn verify_validity(cert: &Certificate<'_>, now: SystemTime) -> Result<()> {
+    let now = now.duration_since(UNIX_EPOCH)?;
+    let validity = &cert.tbs_certificate.validity;
+
+    if now < validity.not_before.to_unix_duration() {
+        bail!("Certificate is not valid yet");
+    }
+    if now > validity.not_after.to_unix_duration() {
+        bail!("Certificate has expired");
+    }
+    Ok(())
+}
+
+/// Verify `leaf` against `chain`
+///
+/// Every certificate must be valid at `now` and signed by the next one in
+/// the chain. The last certificate of the chain is the trust anchor and must
+/// be self-signed.
+pub fn verify_chain(
+    leaf: &Certificate<'_>,
+    chain: &[Certificate<'_>],
+    now: SystemTime,
+) -> Result<()> {
+    let root = chain.last().context("Empty certificate chain")?;
+    verify_signed(root, root).context("Failed to verify the root certificate")?;
+
+    let mut cert = leaf;
+    for (depth, issuer) in chain.iter().enumerate() {
+        verify_validity(cert, now)
+            .and_then(|_| verify_signed(cert, issuer))
+            .with_context(|| format!("Failed to verify the certificate at depth {depth}"))?;
+        cert = issuer;
+    }
+
+    verify_validity(root, now).context("Failed to verify the root certificate")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use der::Decode;
+
+    const MILAN_CHAIN: &[u8] = include_bytes!("../testdata/milan-chain.pem");
+    const MILAN_VCEK: &[u8] = include_bytes!("../testdata/milan-vcek.der");
+
+    #[test]
+    fn milan() {
+        let chain = rustls_pemfile::certs(&mut &MILAN_CHAIN[..]).unwrap();
+        let ask = Certificate::from_der(&chain[0]).unwrap();
+        let ark = Certificate::from_der(&chain[1]).unwrap();
+        let vcek = Certificate::from_der(MILAN_VCEK).unwrap();
+
+        verify_signed(&ark, &ark).unwrap();
+        verify_signed(&ask, &ark).unwrap();
+        verify_signed(&vcek, &ask).unwrap();
+        assert!(verify_signed(&vcek, &ark).is_err());
+        assert!(verify_signed(&ask, &vcek).is_err());
+    }
+
+    #[test]
+    fn validity() {
+        let chain = rustls_pemfile::certs(&mut &MILAN_CHAIN[..]).unwrap();
+        let chain = chain
+            .iter()
+            .map(|der| Certificate::from_der(der).unwrap())
+            .collect::<Vec<_>>();
+        let vcek = Certificate::from_der(MILAN_VCEK).unwrap();
+
+        let time = |secs| UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        // 2022-01-01, 2020-01-01 and 2030-01-01
+        verify_chain(&vcek, &chain, time(1_640_995_200)).unwrap();
+        assert!(verify_chain(&vcek, &chain, time(1_577_836_800)).is_err());
+        assert!(verify_chain(&vcek, &chain, time(1_893_456_000)).is_err());
+        assert!(verify_chain(&vcek, &[], time(1_640_995_200)).is_err());
+    }
+}
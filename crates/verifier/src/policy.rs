@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verification policies
+//!
+//! A policy is a TOML document with optional `[snp]` and `[sgx]` tables.
+//! The expected measurements can be calculated with `enarx measure`. Values
+//! which are not set are not checked, but debug Keeps are always refused
+//! unless explicitly allowed.
+//!
+//! ```toml
+//! [snp]
+//! measurement = "ffa5915db811e386a67c50634ad22c494efde1ffec98bd8ac26da29d46db51884f184659be2774795dec36d639dffcec"
+//! min-guest-svn = 1
+//! min-tcb = { bootloader = 3, tee = 0, snp = 8, microcode = 115 }
+//!
+//! [sgx]
+//! mrenclave = "7cffc3b4f639db733b62f0d4af8fa66228eea02f8ce609b4f3f6c46ea99f707f"
+//! isv-prod-id = 59904
+//! min-isv-svn = 1
+//! ```
+
+use crate::{hex, sgx, snp};
+
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+/// Policy for the evidence of all technologies
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// Policy for SNP Keeps
+    #[serde(default)]
+    pub snp: Snp,
+
+    /// Policy for SGX Keeps
+    #[serde(default)]
+    pub sgx: Sgx,
+}
+
+impl FromStr for Policy {
+    type Err = toml::de::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+/// Policy for SNP Keeps
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Snp {
+    /// Expected launch measurement in hex
+    pub measurement: Option<String>,
+
+    /// Minimum SVN of the guest
+    pub min_guest_svn: Option<u32>,
+
+    /// Minimum TCB version of the platform
+    pub min_tcb: Option<snp::Tcb>,
+
+    /// Whether to accept Keeps which allow debugging
+    #[serde(default)]
+    pub debug: bool,
+}
+
+impl Snp {
+    /// Check an SNP attestation report against the policy
+    pub fn check(&self, report: &snp::Report) -> Result<()> {
+        if let Some(ref measurement) = self.measurement {
+            expect("launch measurement", measurement, &report.measurement)?;
+        }
+
+        if let Some(min) = self.min_guest_svn {
+            if report.guest_svn < min {
+                bail!(
+                    "Guest SVN {} is lower than the minimum of {min}",
+                    report.guest_svn
+                );
+            }
+        }
+
+        if let Some(ref min) = self.min_tcb {
+            if !report.reported_tcb.at_least(min) {
+                bail!(
+                    "Reported TCB ({}) is lower than the minimum of ({min})",
+                    report.reported_tcb
+                );
+            }
+        }
+
+        if report.debug() && !self.debug {
+            bail!("Keep allows debugging, which the policy refuses");
+        }
+
+        Ok(())
+    }
+}
+
+/// Policy for SGX Keeps
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Sgx {
+    /// Expected enclave measurement in hex
+    pub mrenclave: Option<String>,
+
+    /// Expected hash of the enclave signing key in hex
+    pub mrsigner: Option<String>,
+
+    /// Expected product ID of the enclave
+    pub isv_prod_id: Option<u16>,
+
+    /// Minimum security version of the enclave
+    pub min_isv_svn: Option<u16>,
+
+    /// Whether to accept debug enclaves
+    #[serde(default)]
+    pub debug: bool,
+}
+
+impl Sgx {
+    /// Check an SGX enclave report against the policy
+    pub fn check(&self, report: &sgx::Report) -> Result<()> {
+        if let Some(ref mrenclave) = self.mrenclave {
+            expect("MRENCLAVE", mrenclave, &report.mrenclave)?;
+        }
+
+        if let Some(ref mrsigner) = self.mrsigner {
+            expect("MRSIGNER", mrsigner, &report.mrsigner)?;
+        }
+
+        if let Some(isv_prod_id) = self.isv_prod_id {
+            if report.isv_prod_id != isv_prod_id {
+                bail!(
+                    "ISV product ID {} does not match the expected {isv_prod_id}",
+                    report.isv_prod_id
+                );
+            }
+        }
+
+        if let Some(min) = self.min_isv_svn {
+            if report.isv_svn < min {
+                bail!(
+                    "ISV SVN {} is lower than the minimum of {min}",
+                    report.isv_svn
+                );
+            }
+        }
+
+        if report.debug() && !self.debug {
+            bail!("Keep is a debug enclave, which the policy refuses");
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare a value with its expected hex encoding
+fn expect(name: &str, expected: &str, value: &[u8]) -> Result<()> {
+    let value = hex(value);
+    if !value.eq_ignore_ascii_case(expected) {
+        bail!("The {name} {value} does not match the expected {expected}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let policy: Policy = r#"
+            [snp]
+            measurement = "00ff"
+            min-tcb = { bootloader = 3, tee = 0, snp = 8, microcode = 115 }
+
+            [sgx]
+            min-isv-svn = 2
+            debug = true
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(policy.snp.measurement.as_deref(), Some("00ff"));
+        assert_eq!(policy.snp.min_guest_svn, None);
+        assert_eq!(
+            policy.snp.min_tcb,
+            Some(snp::Tcb {
+                bootloader: 3,
+                tee: 0,
+                snp: 8,
+                microcode: 115
+            })
+        );
+        assert!(!policy.snp.debug);
+        assert_eq!(policy.sgx.min_isv_svn, Some(2));
+        assert!(policy.sgx.debug);
+
+        assert_eq!("".parse::<Policy>().unwrap(), Policy::default());
+        assert!("[snp]\nmrenclave = \"00\"".parse::<Policy>().is_err());
+        assert!("[tdx]".parse::<Policy>().is_err());
+    }
+
+    #[test]
+    fn compare() {
+        expect("value", "00FF", &[0x00, 0xff]).unwrap();
+        assert!(expect("value", "00ff", &[0x00, 0xfe]).is_err());
+        assert!(expect("value", "00ff00", &[0x00, 0xff]).is_err());
+    }
+}
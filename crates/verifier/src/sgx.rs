@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Intel SGX DCAP quotes
+//!
+//! The evidence of an SGX Keep is a version 3 quote signed by an ECDSA P-256
+//! attestation key. The key is bound to the report of the quoting enclave,
+//! which is signed by the PCK certificate included in the quote.
+
+use crate::{array, hex, pki};
+
+use std::fmt::{self, Formatter};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use der::Decode;
+use ring::digest::{digest, SHA256};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+use x509_cert::Certificate;
+
+/// Size of the quote header
+const HEADER: usize = 48;
+
+/// Supported version of the quote format
+const VERSION: u16 = 3;
+
+/// ECDSA-256-with-P-256 attestation key type
+const ECDSA_P256: u16 = 2;
+
+/// Certification data type of a PEM encoded PCK certificate chain
+const PCK_CERT_CHAIN: u16 = 5;
+
+/// Size of a raw ECDSA P-256 signature or public key
+const P256: usize = 64;
+
+/// The verified contents of an SGX enclave report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Security version of the CPU
+    pub cpu_svn: [u8; 16],
+    /// Selected extended features
+    pub misc_select: u32,
+    /// Attribute flags
+    pub attributes: u64,
+    /// Enabled XSAVE features
+    pub xfrm: u64,
+    /// Enclave measurement
+    pub mrenclave: [u8; 32],
+    /// Hash of the key which signed the enclave
+    pub mrsigner: [u8; 32],
+    /// Product ID of the enclave
+    pub isv_prod_id: u16,
+    /// Security version of the enclave
+    pub isv_svn: u16,
+    /// Data supplied by the enclave
+    pub report_data: [u8; 64],
+}
+
+impl Report {
+    /// Size of an enclave report body
+    const SIZE: usize = 384;
+
+    /// Attribute flag of debug enclaves
+    const DEBUG: u64 = 1 << 1;
+
+    fn parse(bytes: &[u8]) -> Self {
+        Self {
+            cpu_svn: array(bytes, 0),
+            misc_select: u32::from_le_bytes(array(bytes, 16)),
+            attributes: u64::from_le_bytes(array(bytes, 48)),
+            xfrm: u64::from_le_bytes(array(bytes, 56)),
+            mrenclave: array(bytes, 64),
+            mrsigner: array(bytes, 128),
+            isv_prod_id: u16::from_le_bytes(array(bytes, 256)),
+            isv_svn: u16::from_le_bytes(array(bytes, 258)),
+            report_data: array(bytes, 320),
+        }
+    }
+
+    /// Whether the enclave is a debug enclave
+    pub fn debug(&self) -> bool {
+        self.attributes & Self::DEBUG != 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  MRENCLAVE: {}", hex(&self.mrenclave))?;
+        writeln!(f, "  MRSIGNER: {}", hex(&self.mrsigner))?;
+        writeln!(f, "  ISV product ID: {}", self.isv_prod_id)?;
+        writeln!(f, "  ISV SVN: {}", self.isv_svn)?;
+        writeln!(f, "  Debug: {}", self.debug())
+    }
+}
+
+/// Split `n` bytes off the front of `bytes`
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if bytes.len() < n {
+        bail!("Truncated SGX quote");
+    }
+
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Verify an SGX quote at `now`
+///
+/// `chain` contains the PCK platform or processor CA and the root CA, which
+/// is the trust anchor.
+pub fn verify(quote: &[u8], chain: &[Certificate<'_>], now: SystemTime) -> Result<Report> {
+    let mut rest = quote;
+    let header = take(&mut rest, HEADER)?;
+    let body = take(&mut rest, Report::SIZE)?;
+    let len = u32::from_le_bytes(array(take(&mut rest, 4)?, 0));
+    let mut rest = take(&mut rest, len as _)?;
+
+    let version = u16::from_le_bytes(array(header, 0));
+    let key_type = u16::from_le_bytes(array(header, 2));
+    if version != VERSION || key_type != ECDSA_P256 {
+        bail!("Unsupported SGX quote version {version} with attestation key type {key_type}");
+    }
+
+    let signature = take(&mut rest, P256)?;
+    let attestation_key = take(&mut rest, P256)?;
+    let qe_report = take(&mut rest, Report::SIZE)?;
+    let qe_signature = take(&mut rest, P256)?;
+    let len = u16::from_le_bytes(array(take(&mut rest, 2)?, 0));
+    let auth_data = take(&mut rest, len as _)?;
+    let cert_type = u16::from_le_bytes(array(take(&mut rest, 2)?, 0));
+    let len = u32::from_le_bytes(array(take(&mut rest, 4)?, 0));
+    let mut cert_data = take(&mut rest, len as _)?;
+
+    if cert_type != PCK_CERT_CHAIN {
+        bail!("Unsupported certification data type {cert_type} of the SGX quote");
+    }
+
+    // The PCK certificate is the first one of the certification data.
+    let certs = rustls_pemfile::certs(&mut cert_data)
+        .context("Failed to decode the certification data of the SGX quote")?;
+    let pck = certs.first().context("Missing PCK certificate")?;
+    let pck = Certificate::from_der(pck).context("Failed to decode the PCK certificate")?;
+    pki::verify_chain(&pck, chain, now).context("Failed to verify the PCK certificate")?;
+
+    // The PCK signs the report of the quoting enclave...
+    let key = pck
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key;
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, key)
+        .verify(qe_report, qe_signature)
+        .map_err(|_| anyhow!("Invalid signature of the quoting enclave report"))?;
+
+    // ... which binds the attestation key in its report data ...
+    let mut hash = [0u8; 64];
+    hash[..32].copy_from_slice(digest(&SHA256, &[attestation_key, auth_data].concat()).as_ref());
+    if Report::parse(qe_report).report_data != hash {
+        bail!("Attestation key is not bound to the quoting enclave report");
+    }
+
+    // ... which signs the header and the report of the enclave.
+    let mut key = [4u8; 1 + P256];
+    key[1..].copy_from_slice(attestation_key);
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &key[..])
+        .verify(&quote[..HEADER + Report::SIZE], signature)
+        .map_err(|_| anyhow!("Invalid signature of the SGX quote"))?;
+
+    Ok(Report::parse(body))
+}
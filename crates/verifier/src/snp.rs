@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! AMD SEV-SNP attestation reports
+//!
+//! The evidence of an SNP Keep is the DER encoding of a sequence of the VCEK
+//! certificate and an octet string with the attestation report signed by it.
+//!
+//! The VCEK is derived from the chip and its TCB version, which AMD records
+//! in its extensions, so a report is only accepted if its reported TCB and
+//! chip ID match the ones of the VCEK which signed it.
+
+use crate::{array, hex, pki};
+
+use std::fmt::{self, Formatter};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use const_oid::ObjectIdentifier;
+use der::asn1::OctetStringRef;
+use der::{Decode, Sequence};
+use ring::signature::{UnparsedPublicKey, ECDSA_P384_SHA384_FIXED};
+use serde::Deserialize;
+use x509_cert::Certificate;
+
+/// TCB version of an SNP platform
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Tcb {
+    /// SVN of the PSP bootloader
+    pub bootloader: u8,
+    /// SVN of the PSP operating system
+    pub tee: u8,
+    /// SVN of the SNP firmware
+    pub snp: u8,
+    /// Lowest microcode patch level of all cores
+    pub microcode: u8,
+}
+
+impl Tcb {
+    /// VCEK extension with the SVN of the PSP bootloader
+    const BOOTLOADER: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.1");
+    /// VCEK extension with the SVN of the PSP operating system
+    const TEE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.2");
+    /// VCEK extension with the SVN of the SNP firmware
+    const SNP: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.3");
+    /// VCEK extension with the microcode patch level
+    const MICROCODE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.8");
+
+    fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self {
+            bootloader: bytes[0],
+            tee: bytes[1],
+            snp: bytes[6],
+            microcode: bytes[7],
+        }
+    }
+
+    /// Read the TCB version the VCEK was issued for from its extensions
+    fn from_vcek(vcek: &Certificate<'_>) -> Result<Self> {
+        let svn = |oid: ObjectIdentifier| -> Result<u8> {
+            u8::from_der(extension(vcek, oid)?)
+                .with_context(|| format!("Failed to decode the VCEK extension {oid}"))
+        };
+
+        Ok(Self {
+            bootloader: svn(Self::BOOTLOADER)?,
+            tee: svn(Self::TEE)?,
+            snp: svn(Self::SNP)?,
+            microcode: svn(Self::MICROCODE)?,
+        })
+    }
+
+    /// Whether every component is at least the one of `min`
+    pub fn at_least(&self, min: &Self) -> bool {
+        self.bootloader >= min.bootloader
+            && self.tee >= min.tee
+            && self.snp >= min.snp
+            && self.microcode >= min.microcode
+    }
+}
+
+impl fmt::Display for Tcb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bootloader {}, tee {}, snp {}, microcode {}",
+            self.bootloader, self.tee, self.snp, self.microcode
+        )
+    }
+}
+
+/// The verified contents of an SNP attestation report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Version of the report format
+    pub version: u32,
+    /// SVN of the guest
+    pub guest_svn: u32,
+    /// Guest policy
+    pub policy: u64,
+    /// Family ID of the ID block
+    pub family_id: [u8; 16],
+    /// Image ID of the ID block
+    pub image_id: [u8; 16],
+    /// VMPL which requested the report
+    pub vmpl: u32,
+    /// TCB version of the VCEK which signed the report
+    pub reported_tcb: Tcb,
+    /// Data supplied by the guest
+    pub report_data: [u8; 64],
+    /// Launch measurement
+    pub measurement: [u8; 48],
+    /// Identifier of the chip
+    pub chip_id: [u8; 64],
+}
+
+impl Report {
+    /// Size of an attestation report
+    const SIZE: usize = 0x4A0;
+
+    /// Size of the signed part of an attestation report
+    const SIGNED: usize = 0x2A0;
+
+    /// Size of a signature component, zero extended and little endian
+    const COMPONENT: usize = 72;
+
+    /// ECDSA P-384 with SHA-384
+    const SIG_ALGO_ECDSA_P384_SHA384: u32 = 1;
+
+    /// Guest policy bit allowing debugging
+    const POLICY_DEBUG: u64 = 1 << 19;
+
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::SIZE {
+            bail!(
+                "Invalid length of the SNP attestation report: {} bytes",
+                bytes.len()
+            );
+        }
+
+        let sig_algo = u32::from_le_bytes(array(bytes, 0x34));
+        if sig_algo != Self::SIG_ALGO_ECDSA_P384_SHA384 {
+            bail!("Unsupported signature algorithm {sig_algo} of the SNP attestation report");
+        }
+
+        Ok(Self {
+            version: u32::from_le_bytes(array(bytes, 0x00)),
+            guest_svn: u32::from_le_bytes(array(bytes, 0x04)),
+            policy: u64::from_le_bytes(array(bytes, 0x08)),
+            family_id: array(bytes, 0x10),
+            image_id: array(bytes, 0x20),
+            vmpl: u32::from_le_bytes(array(bytes, 0x30)),
+            reported_tcb: Tcb::from_bytes(array(bytes, 0x180)),
+            report_data: array(bytes, 0x50),
+            measurement: array(bytes, 0x90),
+            chip_id: array(bytes, 0x1A0),
+        })
+    }
+
+    /// Whether the guest policy allows debugging
+    pub fn debug(&self) -> bool {
+        self.policy & Self::POLICY_DEBUG != 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  Launch measurement: {}", hex(&self.measurement))?;
+        writeln!(f, "  Policy: {:#x}", self.policy)?;
+        writeln!(f, "  Family ID: {}", hex(&self.family_id))?;
+        writeln!(f, "  Image ID: {}", hex(&self.image_id))?;
+        writeln!(f, "  Guest SVN: {}", self.guest_svn)?;
+        writeln!(f, "  Reported TCB: {}", self.reported_tcb)?;
+        writeln!(f, "  Chip ID: {}", hex(&self.chip_id))
+    }
+}
+
+/// The evidence of an SNP Keep
+#[derive(Sequence)]
+struct Evidence<'a> {
+    vcek: Certificate<'a>,
+    report: OctetStringRef<'a>,
+}
+
+/// VCEK extension with the ID of the chip
+const HW_ID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.4");
+
+/// Find the value of the extension `oid` of the VCEK
+fn extension<'a>(vcek: &Certificate<'a>, oid: ObjectIdentifier) -> Result<&'a [u8]> {
+    vcek.tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|e| e.extn_id == oid)
+        .map(|e| e.extn_value)
+        .with_context(|| format!("Missing the VCEK extension {oid}"))
+}
+
+/// Check that the report was made on the chip and TCB the VCEK was issued for
+fn verify_vcek(vcek: &Certificate<'_>, report: &Report) -> Result<()> {
+    let tcb = Tcb::from_vcek(vcek)?;
+    if report.reported_tcb != tcb {
+        bail!(
+            "Reported TCB ({}) does not match the TCB of the VCEK ({tcb})",
+            report.reported_tcb
+        );
+    }
+
+    if extension(vcek, HW_ID)? != report.chip_id {
+        bail!("Chip ID does not match the hardware ID of the VCEK");
+    }
+    Ok(())
+}
+
+/// Verify the signature of an attestation report with the VCEK
+fn verify_signature(vcek: &Certificate<'_>, report: &[u8]) -> Result<()> {
+    // Convert the little endian components to the fixed big endian encoding.
+    let mut sig = [0u8; 96];
+    let components = report[Report::SIGNED..].chunks(Report::COMPONENT).take(2);
+    for (dst, src) in sig.chunks_mut(48).zip(components) {
+        if src[48..].iter().any(|b| *b != 0) {
+            bail!("Invalid signature of the SNP attestation report");
+        }
+        dst.iter_mut()
+            .zip(src[..48].iter().rev())
+            .for_each(|(d, s)| *d = *s);
+    }
+
+    let key = vcek
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key;
+    UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, key)
+        .verify(&report[..Report::SIGNED], &sig)
+        .map_err(|_| anyhow!("Invalid signature of the SNP attestation report"))
+}
+
+/// Verify SNP evidence at `now`
+///
+/// `chain` contains the ASK and the ARK, which is the trust anchor.
+pub fn verify(evidence: &[u8], chain: &[Certificate<'_>], now: SystemTime) -> Result<Report> {
+    let evidence = Evidence::from_der(evidence).context("Failed to decode the SNP evidence")?;
+
+    pki::verify_chain(&evidence.vcek, chain, now).context("Failed to verify the VCEK")?;
+
+    let bytes = evidence.report.as_bytes();
+    let report = Report::parse(bytes)?;
+    verify_signature(&evidence.vcek, bytes)?;
+    verify_vcek(&evidence.vcek, &report)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MILAN_VCEK: &[u8] = include_bytes!("../testdata/milan-vcek.der");
+
+    #[test]
+    fn milan() {
+        let vcek = Certificate::from_der(MILAN_VCEK).unwrap();
+
+        // The VCEK was fetched for blSPL=00, teeSPL=00, snpSPL=03, ucodeSPL=29.
+        let tcb = Tcb::from_vcek(&vcek).unwrap();
+        assert_eq!(
+            tcb,
+            Tcb {
+                bootloader: 0,
+                tee: 0,
+                snp: 3,
+                microcode: 29
+            }
+        );
+
+        let hw_id = extension(&vcek, HW_ID).unwrap();
+        assert_eq!(hex(hw_id), "8ba826b2dd6ab65e401e0c4d4128ef4b434ed0ccb213f66c5f577b518730ef5892f78a78be259976973125a3b9b3d19f286c912cf5776fdfcee5260fa4576c4b");
+    }
+}
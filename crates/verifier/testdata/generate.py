@@ -0,0 +1,201 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: Apache-2.0
+
+"""Generate synthetic SNP and SGX attestation fixtures.
+
+All keys are throwaway keys created on every run, so the fixtures are only
+signed by the synthetic chains written next to them. Run this script from
+its own directory with the `cryptography` package installed.
+"""
+
+import datetime
+import hashlib
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, padding, rsa
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+SGX_OID = x509.ObjectIdentifier("1.3.6.1.4.1.58270.1.2")
+SNP_OID = x509.ObjectIdentifier("1.3.6.1.4.1.58270.1.3")
+
+# TCB components and hardware ID of the VCEK
+BL_SPL_OID = x509.ObjectIdentifier("1.3.6.1.4.1.3704.1.3.1")
+TEE_SPL_OID = x509.ObjectIdentifier("1.3.6.1.4.1.3704.1.3.2")
+SNP_SPL_OID = x509.ObjectIdentifier("1.3.6.1.4.1.3704.1.3.3")
+UCODE_SPL_OID = x509.ObjectIdentifier("1.3.6.1.4.1.3704.1.3.8")
+HW_ID_OID = x509.ObjectIdentifier("1.3.6.1.4.1.3704.1.4")
+
+SNP_TCB = [3, 0, 0, 0, 0, 0, 8, 115]
+SNP_CHIP_ID = bytes(range(0x80, 0xC0))
+
+NOT_BEFORE = datetime.datetime(2020, 1, 1)
+NOT_AFTER = datetime.datetime(2099, 1, 1)
+
+PSS = padding.PSS(mgf=padding.MGF1(hashes.SHA384()), salt_length=48)
+
+
+def name(cn):
+    return x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, cn)])
+
+
+def certificate(subject, key, issuer, issuer_key, ca, extensions=(), **kwargs):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer))
+        .public_key(key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(NOT_BEFORE)
+        .not_valid_after(NOT_AFTER)
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+    )
+    for oid, value in extensions:
+        builder = builder.add_extension(x509.UnrecognizedExtension(oid, value), critical=False)
+    return builder.sign(issuer_key, **kwargs)
+
+
+def der(cert):
+    return cert.public_bytes(serialization.Encoding.DER)
+
+
+def pem(*certs):
+    return b"".join(c.public_bytes(serialization.Encoding.PEM) for c in certs)
+
+
+def spki(key):
+    return key.public_key().public_bytes(
+        serialization.Encoding.DER, serialization.PublicFormat.SubjectPublicKeyInfo
+    )
+
+
+def asn1(tag, body):
+    n = len(body)
+    if n < 0x80:
+        length = bytes([n])
+    else:
+        raw = n.to_bytes((n.bit_length() + 7) // 8, "big")
+        length = bytes([0x80 | len(raw)]) + raw
+    return bytes([tag]) + length + body
+
+
+def integer(n):
+    return asn1(0x02, n.to_bytes(n.bit_length() // 8 + 1, "big"))
+
+
+def raw_signature(key, message, algorithm, size, order):
+    r, s = decode_dss_signature(key.sign(message, ec.ECDSA(algorithm)))
+    return r.to_bytes(size, order) + s.to_bytes(size, order)
+
+
+def csr(key, oid, evidence, algorithm):
+    return (
+        x509.CertificateSigningRequestBuilder()
+        .subject_name(x509.Name([]))
+        .add_extension(x509.UnrecognizedExtension(oid, evidence), critical=False)
+        .sign(key, algorithm)
+    )
+
+
+def write(path, data):
+    with open(path, "wb") as f:
+        f.write(data)
+
+
+def snp_report(vcek, keep, tcb):
+    report = bytearray(0x4A0)
+    struct.pack_into("<IIQ", report, 0x00, 2, 1, 0x30000)
+    report[0x10:0x20] = bytes(range(0x10))
+    report[0x20:0x30] = bytes(range(0x20, 0x30))
+    struct.pack_into("<II", report, 0x30, 0, 1)
+    report[0x38:0x40] = bytes(tcb)
+    report[0x50:0x80] = hashlib.sha384(spki(keep)).digest()
+    report[0x90:0xC0] = bytes(range(0x40, 0x70))
+    report[0x180:0x188] = bytes(tcb)
+    report[0x1A0:0x1E0] = SNP_CHIP_ID
+    report[0x2A0:0x330] = raw_signature(vcek, bytes(report[:0x2A0]), hashes.SHA384(), 72, "little")
+    return bytes(report)
+
+
+def snp():
+    ark = rsa.generate_private_key(65537, 2048)
+    ask = rsa.generate_private_key(65537, 2048)
+    vcek = ec.generate_private_key(ec.SECP384R1())
+    keep = ec.generate_private_key(ec.SECP384R1())
+
+    vcek_extensions = [
+        (BL_SPL_OID, integer(SNP_TCB[0])),
+        (TEE_SPL_OID, integer(SNP_TCB[1])),
+        (SNP_SPL_OID, integer(SNP_TCB[6])),
+        (UCODE_SPL_OID, integer(SNP_TCB[7])),
+        (HW_ID_OID, SNP_CHIP_ID),
+    ]
+
+    ark_crt = certificate("ARK-Test", ark, "ARK-Test", ark, True, algorithm=hashes.SHA384(), rsa_padding=PSS)
+    ask_crt = certificate("SEV-Test", ask, "ARK-Test", ark, True, algorithm=hashes.SHA384(), rsa_padding=PSS)
+    vcek_crt = certificate(
+        "SEV-VCEK", vcek, "SEV-Test", ask, False, vcek_extensions, algorithm=hashes.SHA384(), rsa_padding=PSS
+    )
+
+    write("snp-chain.pem", pem(ask_crt, ark_crt))
+
+    # `snp-tcb.csr` is signed by the VCEK, but claims a newer microcode than
+    # the one the VCEK was issued for.
+    for path, tcb in [("snp.csr", SNP_TCB), ("snp-tcb.csr", SNP_TCB[:7] + [116])]:
+        evidence = asn1(0x30, der(vcek_crt) + asn1(0x04, snp_report(vcek, keep, tcb)))
+        write(path, csr(keep, SNP_OID, evidence, hashes.SHA384()).public_bytes(serialization.Encoding.PEM))
+
+
+def sgx():
+    root = ec.generate_private_key(ec.SECP256R1())
+    platform = ec.generate_private_key(ec.SECP256R1())
+    pck = ec.generate_private_key(ec.SECP256R1())
+    attestation = ec.generate_private_key(ec.SECP256R1())
+    keep = ec.generate_private_key(ec.SECP256R1())
+
+    root_crt = certificate("SGX Test Root CA", root, "SGX Test Root CA", root, True, algorithm=hashes.SHA256())
+    platform_crt = certificate("SGX Test Platform CA", platform, "SGX Test Root CA", root, True, algorithm=hashes.SHA256())
+    pck_crt = certificate("SGX Test PCK Certificate", pck, "SGX Test Platform CA", platform, False, algorithm=hashes.SHA256())
+
+    # Header: version 3, ECDSA-256-with-P-256 attestation key, SGX TEE
+    header = struct.pack("<HHIHH", 3, 2, 0, 0, 0) + bytes(16) + bytes(20)
+
+    # ISV enclave report body: INIT | MODE64BIT, no DEBUG
+    body = bytearray(384)
+    struct.pack_into("<QQ", body, 48, 0x5, 0x3)
+    body[64:96] = bytes(range(32))
+    body[128:160] = bytes(range(32, 64))
+    struct.pack_into("<HH", body, 256, 0xEA00, 1)
+    body[320:352] = hashlib.sha256(spki(keep)).digest()
+
+    signed = header + bytes(body)
+    attestation_key = attestation.public_key().public_bytes(
+        serialization.Encoding.X962, serialization.PublicFormat.UncompressedPoint
+    )[1:]
+    auth_data = b"enarx test"
+
+    qe_report = bytearray(384)
+    qe_report[320:352] = hashlib.sha256(attestation_key + auth_data).digest()
+
+    certification = pem(pck_crt, platform_crt, root_crt)
+    signature_data = (
+        raw_signature(attestation, signed, hashes.SHA256(), 32, "big")
+        + attestation_key
+        + bytes(qe_report)
+        + raw_signature(pck, bytes(qe_report), hashes.SHA256(), 32, "big")
+        + struct.pack("<H", len(auth_data))
+        + auth_data
+        + struct.pack("<HI", 5, len(certification))
+        + certification
+    )
+    quote = signed + struct.pack("<I", len(signature_data)) + signature_data
+
+    write("sgx-chain.pem", pem(platform_crt, root_crt))
+    write("sgx.csr", csr(keep, SGX_OID, quote, hashes.SHA256()).public_bytes(serialization.Encoding.PEM))
+
+
+if __name__ == "__main__":
+    snp()
+    sgx()
@@ -9,7 +9,7 @@ set -e
 #
 
 # The crates we plan on releasing
-readonly CRATES_REG_TARGETS=(enarx-config sallyport enarx-exec-wasmtime)
+readonly CRATES_REG_TARGETS=(enarx-config enarx-verifier sallyport enarx-exec-wasmtime)
 readonly CRATES_UNKNOWN_NONE=(enarx-shim-kvm enarx-shim-sgx)
 readonly CRATES=( "${CRATES_REG_TARGETS[@]}" "${CRATES_UNKNOWN_NONE[@]}" enarx )
 
@@ -3,13 +3,14 @@
 use super::Config;
 
 use std::convert::TryInto;
+use std::mem::size_of;
 
 use anyhow::{anyhow, Error, Result};
 use goblin::elf::{header::*, note::NoteIterator, program_header::*, Elf};
 use mmarinus::{perms, Map};
 use primordial::Page;
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use std::ops::Range;
 
 #[derive(Clone, Debug)]
@@ -123,8 +124,6 @@ impl<'a> Binary<'a> {
     /// This function transmutes the bytes into the specified type. Beware!
     #[allow(dead_code)]
     pub unsafe fn note<T: Copy>(&self, name: &str, kind: u32) -> Option<T> {
-        use core::mem::size_of;
-
         for note in self.notes(name, kind) {
             if note.len() == size_of::<T>() {
                 return Some(note.as_ptr().cast::<T>().read_unaligned());
@@ -135,11 +134,63 @@ impl<'a> Binary<'a> {
     }
 }
 
+/// Check that the ELF notes of an external shim are compatible with this host.
+///
+/// This is done before building a Keep, so that a mismatched shim is rejected
+/// with a clear error rather than failing somewhere during the Keep setup.
+pub fn check_shim(shim: &[u8]) -> Result<()> {
+    Binary::new(shim)
+        .map_err(|e| anyhow!("Invalid shim ELF binary: {e}"))?
+        .check_notes()
+}
+
+impl Binary<'_> {
+    /// Check the sallyport `REQUIRES` and `BLOCK_SIZE` notes.
+    fn check_notes(&self) -> Result<()> {
+        use sallyport::elf::note;
+
+        let version = semver::Version::parse(sallyport::VERSION).unwrap();
+        let requires: Vec<_> = self
+            .notes(note::NAME, note::REQUIRES)
+            .filter_map(|n| std::str::from_utf8(n).ok())
+            .map(|n| n.trim_end_matches('\0'))
+            .collect();
+        if requires.is_empty() {
+            return Err(anyhow!(
+                "Shim does not declare a sallyport version requirement"
+            ));
+        }
+        let supported = requires
+            .iter()
+            .filter_map(|n| semver::VersionReq::parse(n).ok())
+            .any(|req| req.matches(&version));
+        if !supported {
+            return Err(anyhow!(
+                "Unable to satisfy sallyport version requirement! The shim requires `{}`, but this host provides sallyport {version}",
+                requires.join("`, `")
+            ));
+        }
+
+        // Safety: converting 8 bytes into u64 should not produce any unsound behavior.
+        let block_size = unsafe { self.note::<u64>(note::NAME, note::BLOCK_SIZE) }
+            .ok_or_else(|| anyhow!("Shim is missing a valid sallyport BLOCK_SIZE note"))?;
+        if block_size == 0 || block_size % size_of::<usize>() as u64 != 0 {
+            return Err(anyhow!(
+                "Shim sallyport BLOCK_SIZE {block_size} is not a non-zero multiple of {}",
+                size_of::<usize>()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 impl<T: Mapper> Loader for T {
     fn load(
         shim: impl AsRef<[u8]>,
         exec: impl AsRef<[u8]>,
         signatures: Option<Signatures>,
+        identity: &Identity,
     ) -> Result<Self::Output> {
         use sallyport::elf;
 
@@ -161,18 +212,10 @@ impl<T: Mapper> Loader for T {
         }
 
         // Check sallyport compatibility
-        let version = semver::Version::parse(sallyport::VERSION).unwrap();
-        let supported = sbin
-            .notes(elf::note::NAME, elf::note::REQUIRES)
-            .filter_map(|n| std::str::from_utf8(n).ok())
-            .filter_map(|n| semver::VersionReq::parse(n).ok())
-            .any(|req| req.matches(&version));
-        if !supported {
-            return Err(anyhow!("Unable to satisfy sallyport version requirement!"));
-        }
+        sbin.check_notes()?;
 
         // Parse the config and create a builder.
-        let mut loader: Self = Self::Config::new(&sbin, &ebin, signatures)?.try_into()?;
+        let mut loader: Self = Self::Config::new(&sbin, &ebin, signatures, identity)?.try_into()?;
 
         // Get an array of all final segment locations (relocated).
         let ssegs: Vec<Segment<'_>> = sbin.segments(0).collect();
@@ -222,5 +265,6 @@ pub(crate) trait Loader: Mapper {
         shim: impl AsRef<[u8]>,
         exec: impl AsRef<[u8]>,
         signatures: Option<Signatures>,
+        identity: &Identity,
     ) -> Result<Self::Output>;
 }
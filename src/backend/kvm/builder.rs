@@ -2,7 +2,9 @@
 
 use super::config::Config;
 use super::mem::Region;
-use super::KvmKeepPersonality;
+use super::{KeepPersonality, KvmKeepPersonality};
+use crate::backend::check_memory_limit;
+use crate::backend::metrics::METRICS;
 
 use std::convert::TryFrom;
 use std::mem::align_of;
@@ -66,6 +68,7 @@ impl super::super::Mapper for Builder {
             to,
             with,
             self.regions.len() as _,
+            KvmKeepPersonality::mem_flags(),
         )?;
 
         self.regions.push(Region::new(mem_region, pages));
@@ -82,6 +85,7 @@ pub fn kvm_builder_map(
     to: usize,
     with: u32,
     slot: u32,
+    flags: u32,
 ) -> anyhow::Result<kvm_userspace_memory_region> {
     if with & SALLYPORT != 0 {
         for start in (0..pages.size()).step_by(block_size) {
@@ -95,7 +99,7 @@ pub fn kvm_builder_map(
 
     let mem_region = kvm_userspace_memory_region {
         slot,
-        flags: 0,
+        flags,
         guest_phys_addr: to as _,
         memory_size: pages.size() as _,
         userspace_addr: pages.addr() as _,
@@ -116,6 +120,11 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
         let vcpu_fd =
             kvm_try_from_builder(&builder.sallyports, &mut builder.kvm_fd, &mut builder.vm_fd)?;
 
+        let memory = builder.regions.iter().map(|r| r.as_guest().count).sum();
+        check_memory_limit(memory)?;
+        METRICS.map(memory);
+        METRICS.set_memory_slots(builder.kvm_fd.get_nr_memslots() as _);
+
         Ok(Arc::new(RwLock::new(super::Keep::<KvmKeepPersonality> {
             kvm_fd: builder.kvm_fd,
             vm_fd: builder.vm_fd,
@@ -138,6 +147,11 @@ pub fn kvm_try_from_builder(
         anyhow::bail!("No sallyport blocks defined!");
     }
 
+    create_vcpu(kvm_fd, vm_fd)
+}
+
+/// Create the only virtual CPU with the CPUID entries supported by KVM.
+pub fn create_vcpu(kvm_fd: &Kvm, vm_fd: &VmFd) -> Result<VcpuFd> {
     let cpuids = kvm_fd
         .get_supported_cpuid(KVM_MAX_CPUID_ENTRIES)
         .context("Failed to get supported CPUID entries from kvm")?;
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::{anyhow, Result};
 use goblin::elf64::program_header::PT_LOAD;
 use sallyport::elf::{self, pf::kvm::SALLYPORT};
@@ -21,6 +21,7 @@ impl super::super::Config for Config {
         shim: &super::super::Binary<'_>,
         _exec: &super::super::Binary<'_>,
         signatures: Option<Signatures>,
+        _identity: &Identity,
     ) -> Result<Self> {
         let sallyport_headers = shim.headers(PT_LOAD).filter(|p| p.p_flags & SALLYPORT != 0);
 
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::{probe::x86_64::CpuId, Datum};
+use crate::backend::{probe::x86_64::CpuId, Datum, Remediation, Severity};
 
 use kvm_ioctls::Kvm;
 
@@ -8,8 +8,11 @@ pub fn dev_kvm() -> Datum {
     let dev_kvm = std::path::Path::new("/dev/kvm");
 
     Datum {
+        id: "kvm.driver",
         name: "Driver".into(),
         pass: dev_kvm.exists(),
+        severity: Severity::Required,
+        remediation: Remediation::Kernel,
         info: Some("/dev/kvm".into()),
         mesg: None,
     }
@@ -23,8 +26,11 @@ pub fn kvm_version() -> Datum {
     };
 
     Datum {
+        id: "kvm.api-version",
         name: " API Version".into(),
         pass,
+        severity: Severity::Required,
+        remediation: Remediation::Kernel,
         info,
         mesg: None,
     }
@@ -32,6 +38,7 @@ pub fn kvm_version() -> Datum {
 
 pub const CPUIDS: &[CpuId] = &[
     CpuId {
+        id: "kvm.cpu",
         name: "CPU",
         leaf: 0x80000000,
         subl: 0x00000000,
@@ -39,6 +46,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: None,
     },
     CpuId {
+        id: "kvm.cpu.fsgsbase",
         name: " CPU supports FSGSBASE instructions",
         leaf: 0x00000007,
         subl: 0x00000000,
@@ -46,6 +54,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: None,
     },
     CpuId {
+        id: "kvm.cpu.rdrand",
         name: " CPU supports RDRAND instruction",
         leaf: 0x00000001,
         subl: 0x00000000,
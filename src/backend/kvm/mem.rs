@@ -19,7 +19,6 @@ impl Region {
         }
     }
 
-    #[allow(dead_code)]
     pub fn as_guest(&self) -> Span<PhysAddr, u64> {
         Span {
             start: PhysAddr::new(self.kvm_region.guest_phys_addr),
@@ -34,6 +33,10 @@ impl Region {
         }
     }
 
+    pub fn slot(&self) -> u32 {
+        self.kvm_region.slot
+    }
+
     pub fn backing(&self) -> &[u8] {
         self._backing.as_ref()
     }
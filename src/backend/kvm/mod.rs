@@ -8,9 +8,10 @@ use mem::Region;
 
 use std::sync::Arc;
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::Result;
 use kvm_bindings::bindings::kvm_userspace_memory_region;
+use kvm_bindings::KVM_MEM_LOG_DIRTY_PAGES;
 use kvm_ioctls::Kvm;
 use kvm_ioctls::{VcpuFd, VmFd};
 use mmarinus::{perms, Map};
@@ -22,9 +23,15 @@ pub mod builder;
 pub mod config;
 pub mod data;
 pub mod mem;
+pub mod snapshot;
 pub mod thread;
 
 pub trait KeepPersonality {
+    /// The flags of the guest memory regions
+    fn mem_flags() -> u32 {
+        0
+    }
+
     fn map(_vm_fd: &mut VmFd, _region: &Region) -> std::io::Result<()> {
         Ok(())
     }
@@ -40,7 +47,16 @@ pub trait KeepPersonality {
 
 struct KvmKeepPersonality(());
 
-impl KeepPersonality for KvmKeepPersonality {}
+impl KeepPersonality for KvmKeepPersonality {
+    // Track the pages written by the guest, if incremental snapshots are taken.
+    fn mem_flags() -> u32 {
+        if snapshot::enabled() {
+            KVM_MEM_LOG_DIRTY_PAGES
+        } else {
+            0
+        }
+    }
+}
 
 pub struct Keep<P: KeepPersonality> {
     pub kvm_fd: Kvm,
@@ -56,7 +72,7 @@ impl<P: KeepPersonality> Keep<P> {
     pub fn map(&mut self, pages: Map<perms::ReadWrite>, to: usize) -> std::io::Result<&mut Region> {
         let kvm_region = kvm_userspace_memory_region {
             slot: self.regions.len() as u32,
-            flags: 0,
+            flags: P::mem_flags(),
             guest_phys_addr: to as u64,
             memory_size: pages.len() as u64,
             userspace_addr: pages.addr() as u64,
@@ -109,11 +125,11 @@ impl crate::backend::Backend for Backend {
         exec: &[u8],
         signatures: Option<Signatures>,
     ) -> Result<Arc<dyn super::Keep>> {
-        builder::Builder::load(shim, exec, signatures)
+        builder::Builder::load(shim, exec, signatures, &Identity::default())
     }
 
     #[inline]
-    fn hash(&self, _shim: &[u8], _exec: &[u8]) -> Result<Vec<u8>> {
+    fn hash(&self, _shim: &[u8], _exec: &[u8], _identity: &Identity) -> Result<Vec<u8>> {
         Ok(Vec::new())
     }
 }
@@ -0,0 +1,434 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshots of running KVM Keeps
+//!
+//! A snapshot file starts with [`MAGIC`] followed by one or more layers. The
+//! first layer holds all of the guest memory, each later one only the pages
+//! written since the previous layer. Every layer holds the complete vCPU
+//! state, so restoring applies the memory of all layers in order and resumes
+//! with the vCPU state of the last one.
+//!
+//! Only the state of the Keep itself is captured: the host resources used by
+//! the workload, like open files or sockets, are not restored.
+
+use super::builder::create_vcpu;
+use super::{Keep, KeepPersonality, KvmKeepPersonality};
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::mem::{size_of, zeroed};
+use std::path::Path;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{anyhow, bail, Context, Result};
+use kvm_bindings::{kvm_msr_entry, Msrs, KVM_MEM_LOG_DIRTY_PAGES};
+use kvm_ioctls::{Kvm, VcpuFd};
+use mmarinus::{perms, Map};
+use x86_64::VirtAddr;
+
+/// The start of a snapshot file
+const MAGIC: &[u8; 8] = b"ENARXSS1";
+
+/// The start of a snapshot layer
+const LAYER: &[u8; 4] = b"LAYR";
+
+/// The granularity of the dirty page tracking
+const PAGE_SIZE: usize = 4096;
+
+/// The model specific registers saved in a snapshot
+const MSRS: [u32; 11] = [
+    0x0000_0010, // IA32_TSC
+    0x0000_0174, // IA32_SYSENTER_CS
+    0x0000_0175, // IA32_SYSENTER_ESP
+    0x0000_0176, // IA32_SYSENTER_EIP
+    0x0000_0277, // IA32_PAT
+    0xc000_0081, // IA32_STAR
+    0xc000_0082, // IA32_LSTAR
+    0xc000_0083, // IA32_CSTAR
+    0xc000_0084, // IA32_FMASK
+    0xc000_0102, // IA32_KERNEL_GS_BASE
+    0xc000_0103, // IA32_TSC_AUX
+];
+
+/// The snapshot file being written
+struct Output {
+    file: BufWriter<File>,
+    layers: usize,
+}
+
+static OUTPUT: Mutex<Option<Output>> = Mutex::new(None);
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request(_signal: libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Take a snapshot of the Keep on every `SIGUSR1`, appending it to `path`.
+pub fn on_signal(path: &Path) -> Result<()> {
+    let mut file = File::create(path)
+        .map(BufWriter::new)
+        .with_context(|| format!("Failed to create snapshot `{}`", path.display()))?;
+    file.write_all(MAGIC)
+        .and_then(|()| file.flush())
+        .with_context(|| format!("Failed to write snapshot `{}`", path.display()))?;
+    *OUTPUT.lock().unwrap() = Some(Output { file, layers: 0 });
+
+    if unsafe { libc::signal(libc::SIGUSR1, request as libc::sighandler_t) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to install the SIGUSR1 handler");
+    }
+    Ok(())
+}
+
+/// Whether snapshots are taken, so that the guest memory must track its
+/// dirty pages
+pub(super) fn enabled() -> bool {
+    OUTPUT.lock().unwrap().is_some()
+}
+
+/// Whether a snapshot was requested since the last one was taken
+pub(super) fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Append a snapshot of `keep` running on `vcpu_fd` to the snapshot file.
+pub(super) fn take<P: KeepPersonality>(keep: &Keep<P>, vcpu_fd: &mut VcpuFd) -> Result<()> {
+    REQUESTED.store(false, Ordering::SeqCst);
+
+    let mut output = OUTPUT.lock().unwrap();
+    let output = output
+        .as_mut()
+        .ok_or_else(|| anyhow!("No snapshot file configured"))?;
+    if P::mem_flags() & KVM_MEM_LOG_DIRTY_PAGES == 0 {
+        bail!("Snapshots are only supported by the kvm backend")
+    }
+
+    // Complete the I/O of the last exit, so the vCPU state is consistent.
+    vcpu_fd.set_kvm_immediate_exit(1);
+    let ret = match vcpu_fd.run() {
+        Err(e) if e.errno() == libc::EINTR => Ok(()),
+        Err(e) => Err(e.into()),
+        Ok(exit) => Err(anyhow!("Unexpected KVM exit: {:?}", exit)),
+    };
+    vcpu_fd.set_kvm_immediate_exit(0);
+    ret.context("Failed to complete the pending I/O")?;
+
+    write_layer(&mut output.file, keep, vcpu_fd, output.layers == 0)
+        .context("Failed to write snapshot")?;
+    output.file.flush().context("Failed to write snapshot")?;
+    output.layers += 1;
+    log::info!("snapshot {} taken", output.layers);
+    Ok(())
+}
+
+fn write_layer<P: KeepPersonality>(
+    out: &mut impl Write,
+    keep: &Keep<P>,
+    vcpu_fd: &VcpuFd,
+    full: bool,
+) -> Result<()> {
+    out.write_all(LAYER)?;
+    write_u64(out, keep.sallyport_block_size as _)?;
+
+    // Safety: the KVM structures are plain old data.
+    unsafe {
+        write_pod(out, &vcpu_fd.get_sregs()?)?;
+        write_pod(out, &vcpu_fd.get_regs()?)?;
+        write_pod(out, &vcpu_fd.get_fpu()?)?;
+        write_pod(out, &vcpu_fd.get_xsave()?)?;
+    }
+
+    let mut msrs = msrs()?;
+    if vcpu_fd.get_msrs(&mut msrs)? != MSRS.len() {
+        bail!("Failed to get the model specific registers")
+    }
+    write_u64(out, MSRS.len() as _)?;
+    for entry in msrs.as_slice() {
+        write_u64(out, entry.index as _)?;
+        write_u64(out, entry.data)?;
+    }
+
+    // The host addresses of the sallyport blocks are saved relative to their
+    // region, as the regions are mapped elsewhere on restore.
+    write_u64(out, keep.sallyports.len() as _)?;
+    for block in &keep.sallyports {
+        let block = block.ok_or_else(|| anyhow!("A sallyport block is in use"))?;
+        let (index, region) = keep
+            .regions
+            .iter()
+            .enumerate()
+            .find(|(_, region)| {
+                let virt = region.as_virt();
+                virt.start <= block && block < virt.start + virt.count
+            })
+            .ok_or_else(|| anyhow!("A sallyport block is outside of the guest memory"))?;
+        write_u64(out, index as _)?;
+        write_u64(out, block - region.as_virt().start)?;
+    }
+
+    write_u64(out, keep.regions.len() as _)?;
+    for region in &keep.regions {
+        let guest = region.as_guest();
+        let virt = region.as_virt();
+        let backing = region.backing();
+
+        // Reading the dirty log also resets it for the next layer.
+        let dirty = keep
+            .vm_fd
+            .get_dirty_log(region.slot(), backing.len())
+            .context("Failed to get the dirty pages")?;
+
+        // The host writes the replies into the sallyport blocks, which isn't
+        // tracked in the dirty log.
+        let sallyport = |page: usize| {
+            let start = virt.start + (page * PAGE_SIZE) as u64;
+            let end = start + PAGE_SIZE as u64;
+            keep.sallyports
+                .iter()
+                .flatten()
+                .any(|block| *block < end && start < *block + keep.sallyport_block_size as u64)
+        };
+        let pages: Vec<usize> = (0..backing.len() / PAGE_SIZE)
+            .filter(|&page| full || dirty[page / 64] & 1 << (page % 64) != 0 || sallyport(page))
+            .collect();
+
+        write_u64(out, guest.start.as_u64())?;
+        write_u64(out, guest.count)?;
+        write_u64(out, pages.len() as _)?;
+        for page in pages {
+            write_u64(out, page as _)?;
+            out.write_all(&backing[page * PAGE_SIZE..][..PAGE_SIZE])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restore a Keep from the snapshot at `path`.
+pub fn restore(path: &Path) -> Result<Arc<dyn super::super::Keep>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open snapshot `{}`", path.display()))?;
+    read_keep(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to restore snapshot `{}`", path.display()))
+}
+
+/// The vCPU state of a snapshot layer
+struct Vcpu {
+    sregs: kvm_bindings::kvm_sregs,
+    regs: kvm_bindings::kvm_regs,
+    fpu: kvm_bindings::kvm_fpu,
+    xsave: kvm_bindings::kvm_xsave,
+    msrs: Vec<kvm_msr_entry>,
+}
+
+fn read_keep(input: &mut impl Read) -> Result<Arc<dyn super::super::Keep>> {
+    let mut magic = [0; MAGIC.len()];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        bail!("Not a snapshot of a Keep")
+    }
+
+    let mut vcpu = None;
+    let mut sallyport_block_size = 0;
+    let mut sallyports = Vec::new();
+    let mut regions: Vec<(u64, Map<perms::ReadWrite>)> = Vec::new();
+
+    loop {
+        let mut layer = [0; LAYER.len()];
+        match input.read_exact(&mut layer) {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            ret => ret?,
+        }
+        if &layer != LAYER {
+            bail!("Invalid snapshot layer")
+        }
+        sallyport_block_size = read_u64(input)? as usize;
+
+        // Safety: the KVM structures are plain old data.
+        let (sregs, regs, fpu, xsave) = unsafe {
+            (
+                read_pod(input)?,
+                read_pod(input)?,
+                read_pod(input)?,
+                read_pod(input)?,
+            )
+        };
+        let msrs = (0..read_u64(input)?)
+            .map(|_| -> Result<_> {
+                Ok(kvm_msr_entry {
+                    index: read_u64(input)? as _,
+                    data: read_u64(input)?,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<_>>()?;
+        vcpu = Some(Vcpu {
+            sregs,
+            regs,
+            fpu,
+            xsave,
+            msrs,
+        });
+
+        sallyports = (0..read_u64(input)?)
+            .map(|_| -> Result<_> { Ok((read_u64(input)? as usize, read_u64(input)?)) })
+            .collect::<Result<_>>()?;
+
+        let count = read_u64(input)? as usize;
+        if count < regions.len() {
+            bail!("Invalid number of regions")
+        }
+        for index in 0..count {
+            let (start, size) = (read_u64(input)?, read_u64(input)? as usize);
+            if index == regions.len() {
+                let pages = Map::bytes(size)
+                    .anywhere()
+                    .anonymously()
+                    .with(perms::ReadWrite)
+                    .context("Failed to allocate guest memory")?;
+                regions.push((start, pages));
+            }
+            let (addr, pages) = &mut regions[index];
+            if *addr != start || pages.size() != size {
+                bail!("Inconsistent region {}", index)
+            }
+            for _ in 0..read_u64(input)? {
+                let page = read_u64(input)? as usize;
+                if page >= size / PAGE_SIZE {
+                    bail!("Invalid page {} of region {}", page, index)
+                }
+                input.read_exact(&mut pages[page * PAGE_SIZE..][..PAGE_SIZE])?;
+            }
+        }
+    }
+    let vcpu = vcpu.ok_or_else(|| anyhow!("Snapshot without any layer"))?;
+
+    let kvm_fd = Kvm::new().context("Failed to open '/dev/kvm'")?;
+    let vm_fd = kvm_fd
+        .create_vm()
+        .context("Failed to create a virtual machine")?;
+    let mut keep = Keep {
+        kvm_fd,
+        vm_fd,
+        cpu_fds: Vec::new(),
+        sallyport_block_size,
+        sallyports: Vec::new(),
+        regions: Vec::new(),
+        personality: KvmKeepPersonality(()),
+    };
+    for (addr, pages) in regions {
+        keep.map(pages, addr as _)
+            .context("Failed to map guest memory")?;
+    }
+
+    for (index, offset) in sallyports {
+        let region = keep
+            .regions
+            .get(index)
+            .ok_or_else(|| anyhow!("Invalid sallyport block region {}", index))?;
+        if offset + sallyport_block_size as u64 > region.as_virt().count {
+            bail!("Invalid sallyport block offset {:#x}", offset)
+        }
+        keep.sallyports.push(Some(VirtAddr::new(
+            region.as_virt().start.as_u64() + offset,
+        )));
+    }
+
+    let vcpu_fd = create_vcpu(&keep.kvm_fd, &keep.vm_fd)?;
+    vcpu_fd.set_sregs(&vcpu.sregs)?;
+    vcpu_fd.set_regs(&vcpu.regs)?;
+    vcpu_fd.set_fpu(&vcpu.fpu)?;
+    vcpu_fd.set_xsave(&vcpu.xsave)?;
+    let msrs = Msrs::from_entries(&vcpu.msrs)
+        .map_err(|e| anyhow!("Failed to restore the model specific registers: {:?}", e))?;
+    if vcpu_fd.set_msrs(&msrs)? != vcpu.msrs.len() {
+        bail!("Failed to restore the model specific registers")
+    }
+    keep.cpu_fds.push(vcpu_fd);
+
+    Ok(Arc::new(RwLock::new(keep)))
+}
+
+/// The model specific registers to save, without their values
+fn msrs() -> Result<Msrs> {
+    let entries = MSRS.map(|index| kvm_msr_entry {
+        index,
+        ..Default::default()
+    });
+    Msrs::from_entries(&entries)
+        .map_err(|e| anyhow!("Failed to list the model specific registers: {:?}", e))
+}
+
+fn write_u64(out: &mut impl Write, value: u64) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u64(input: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; size_of::<u64>()];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Write the bytes of `value`, prefixed with their length.
+///
+/// # Safety
+///
+/// `T` must be plain old data.
+unsafe fn write_pod<T>(out: &mut impl Write, value: &T) -> std::io::Result<()> {
+    let bytes = slice::from_raw_parts(value as *const T as *const u8, size_of::<T>());
+    write_u64(out, bytes.len() as _)?;
+    out.write_all(bytes)
+}
+
+/// Read a value written with [`write_pod`].
+///
+/// # Safety
+///
+/// `T` must be plain old data.
+unsafe fn read_pod<T>(input: &mut impl Read) -> Result<T> {
+    if read_u64(input)? != size_of::<T>() as u64 {
+        bail!("Invalid size of {}", std::any::type_name::<T>())
+    }
+    let mut value: T = zeroed();
+    let bytes = slice::from_raw_parts_mut(&mut value as *mut T as *mut u8, size_of::<T>());
+    input.read_exact(bytes)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pod() {
+        let regs = kvm_bindings::kvm_regs {
+            rip: 0x1234,
+            rsp: 0x5678,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        unsafe { write_pod(&mut buf, &regs) }.unwrap();
+        assert_eq!(buf.len(), 8 + size_of::<kvm_bindings::kvm_regs>());
+
+        let read: kvm_bindings::kvm_regs = unsafe { read_pod(&mut buf.as_slice()) }.unwrap();
+        assert_eq!(read, regs);
+
+        let err = unsafe { read_pod::<kvm_bindings::kvm_sregs>(&mut buf.as_slice()) };
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        let err = |snapshot: &[u8]| read_keep(&mut &snapshot[..]).err().unwrap().to_string();
+
+        assert_eq!(err(b"ENARXSS0"), "Not a snapshot of a Keep");
+        assert_eq!(err(MAGIC), "Snapshot without any layer");
+        assert_eq!(
+            err(&[&MAGIC[..], b"LAYX"].concat()),
+            "Invalid snapshot layer"
+        );
+        assert!(read_keep(&mut &[&MAGIC[..], LAYER].concat()[..]).is_err());
+    }
+}
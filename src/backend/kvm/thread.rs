@@ -1,14 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::super::Command;
-use super::KeepPersonality;
+use super::{snapshot, KeepPersonality};
 #[cfg(feature = "gdb")]
 use crate::backend::execute_gdb;
+use crate::backend::memory_limit;
+use crate::backend::metrics::{Kind, METRICS};
 
 use std::io;
 use std::iter;
 use std::mem::size_of;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use anyhow::{bail, Context, Result};
 use kvm_ioctls::{VcpuExit, VcpuFd};
@@ -61,6 +64,15 @@ impl<P: KeepPersonality> Thread<P> {
             return Err(libc::EINVAL);
         }
 
+        // Refuse to grow the Keep beyond its memory limit
+        if let Some(limit) = memory_limit() {
+            let keep = self.keep.read().unwrap();
+            let used: u64 = keep.regions.iter().map(|r| r.as_guest().count).sum();
+            if used + (size * npgs) as u64 > limit {
+                return Err(libc::ENOMEM);
+            }
+        }
+
         // Allocate the new memory
         let pages = Map::bytes(size * npgs)
             .anywhere()
@@ -76,6 +88,7 @@ impl<P: KeepPersonality> Thread<P> {
             .map_err(|e| e.raw_os_error().unwrap_or(libc::ENOTSUP))?
             .as_virt()
             .start;
+        METRICS.balloon((size * npgs) as u64);
 
         Ok(vaddr.as_u64() as _)
     }
@@ -126,7 +139,16 @@ impl<P: KeepPersonality> Thread<P> {
 impl<P: KeepPersonality> super::super::Thread for Thread<P> {
     fn enter(&mut self, _gdblisten: &Option<String>) -> Result<Command> {
         let vcpu_fd = self.vcpu_fd.as_mut().unwrap();
-        match vcpu_fd.run()? {
+        if snapshot::requested() {
+            snapshot::take(&self.keep.read().unwrap(), vcpu_fd)?;
+        }
+
+        let exit = match vcpu_fd.run() {
+            // Interrupted by a signal, like a snapshot request
+            Err(e) if e.errno() == libc::EINTR => return Ok(Command::Continue),
+            exit => exit?,
+        };
+        match exit {
             VcpuExit::IoOut(KVM_SYSCALL_TRIGGER_PORT, data) => {
                 debug_assert_eq!(data.len(), 2);
                 let block_nr = data[0] as usize + ((data[1] as usize) << 8);
@@ -144,7 +166,9 @@ impl<P: KeepPersonality> super::super::Thread for Thread<P> {
                 }
                 .into();
 
+                METRICS.exit();
                 for item in block {
+                    let (kind, start) = (Kind::from(&item), Instant::now());
                     match item {
                         Item::Gdbcall(_gdbcall, _data) => {
                             #[cfg(feature = "gdb")]
@@ -204,6 +228,7 @@ impl<P: KeepPersonality> super::super::Thread for Thread<P> {
                                 .context("sallyport::host::execute")?;
                         }
                     }
+                    METRICS.item(kind, start.elapsed());
                 }
 
                 self.keep.write().unwrap().sallyports[block_nr].replace(block_virt);
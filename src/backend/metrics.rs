@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics of the running Keep
+//!
+//! The backends record the guest memory they map and the sallyport items
+//! they execute. The metrics can be served as Prometheus text with
+//! [`serve`] and are summarized when the Keep exits.
+
+#![cfg_attr(not(enarx_with_shim), allow(dead_code))]
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context;
+
+/// The kind of a sallyport item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Syscall,
+    Gdbcall,
+    Enarxcall,
+}
+
+impl Kind {
+    const COUNT: usize = 3;
+    const ALL: [Kind; Kind::COUNT] = [Kind::Syscall, Kind::Gdbcall, Kind::Enarxcall];
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Syscall => "syscall",
+            Kind::Gdbcall => "gdbcall",
+            Kind::Enarxcall => "enarxcall",
+        }
+    }
+}
+
+#[cfg(enarx_with_shim)]
+impl From<&sallyport::item::Item<'_>> for Kind {
+    fn from(item: &sallyport::item::Item<'_>) -> Self {
+        use sallyport::item::Item;
+
+        match item {
+            Item::Syscall(..) => Kind::Syscall,
+            Item::Gdbcall(..) => Kind::Gdbcall,
+            Item::Enarxcall(..) => Kind::Enarxcall,
+        }
+    }
+}
+
+/// The metrics of the Keep run by this process
+pub static METRICS: Metrics = Metrics::new();
+
+pub struct Metrics {
+    memory: AtomicU64,
+    memory_peak: AtomicU64,
+    memory_slots: AtomicU64,
+    balloon_slots: AtomicU64,
+    exits: AtomicU64,
+    items: [AtomicU64; Kind::COUNT],
+    nanos: [AtomicU64; Kind::COUNT],
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            memory: AtomicU64::new(0),
+            memory_peak: AtomicU64::new(0),
+            memory_slots: AtomicU64::new(0),
+            balloon_slots: AtomicU64::new(0),
+            exits: AtomicU64::new(0),
+            items: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+            nanos: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    /// Record `bytes` of guest memory mapped into the Keep.
+    pub fn map(&self, bytes: u64) {
+        let memory = self.memory.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.memory_peak.fetch_max(memory, Ordering::Relaxed);
+    }
+
+    /// Record a balloon of `bytes` of guest memory in a new memory slot.
+    pub fn balloon(&self, bytes: u64) {
+        self.map(bytes);
+        self.balloon_slots.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the number of memory slots available to the Keep.
+    pub fn set_memory_slots(&self, slots: u64) {
+        self.memory_slots.store(slots, Ordering::Relaxed);
+    }
+
+    /// Record an exit of the Keep to process a sallyport block.
+    pub fn exit(&self) {
+        self.exits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a sallyport item of `kind` executed by the host in `time`.
+    pub fn item(&self, kind: Kind, time: Duration) {
+        self.items[kind as usize].fetch_add(1, Ordering::Relaxed);
+        self.nanos[kind as usize].fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Render the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+        let mut out = String::new();
+        let mut metric =
+            |name: &str, kind: &str, help: &str, values: Vec<(Option<Kind>, String)>| {
+                writeln!(out, "# HELP enarx_keep_{name} {help}").unwrap();
+                writeln!(out, "# TYPE enarx_keep_{name} {kind}").unwrap();
+                for (label, value) in values {
+                    match label {
+                        Some(label) => {
+                            writeln!(
+                                out,
+                                "enarx_keep_{name}{{kind=\"{}\"}} {value}",
+                                label.name()
+                            )
+                        }
+                        None => writeln!(out, "enarx_keep_{name} {value}"),
+                    }
+                    .unwrap();
+                }
+            };
+
+        let single = |value: &AtomicU64| vec![(None::<Kind>, load(value).to_string())];
+        let per_kind = |values: &[AtomicU64], f: fn(u64) -> String| -> Vec<_> {
+            Kind::ALL
+                .iter()
+                .map(|&kind| (Some(kind), f(load(&values[kind as usize]))))
+                .collect()
+        };
+
+        metric(
+            "memory_bytes",
+            "gauge",
+            "Guest memory mapped into the Keep",
+            single(&self.memory),
+        );
+        metric(
+            "memory_peak_bytes",
+            "gauge",
+            "Peak guest memory mapped into the Keep",
+            single(&self.memory_peak),
+        );
+        metric(
+            "memory_slots",
+            "gauge",
+            "Memory slots available to the Keep",
+            single(&self.memory_slots),
+        );
+        metric(
+            "balloon_slots",
+            "gauge",
+            "Memory slots used by ballooning",
+            single(&self.balloon_slots),
+        );
+        metric(
+            "sallyport_exits_total",
+            "counter",
+            "Exits of the Keep to process a sallyport block",
+            single(&self.exits),
+        );
+        metric(
+            "sallyport_items_total",
+            "counter",
+            "Sallyport items executed by the host",
+            per_kind(&self.items, |n| n.to_string()),
+        );
+        metric(
+            "sallyport_seconds_total",
+            "counter",
+            "Time spent by the host executing sallyport items",
+            per_kind(&self.nanos, |n| (n as f64 / 1e9).to_string()),
+        );
+        out
+    }
+
+    /// Summarize the metrics in a single line.
+    pub fn summary(&self) -> String {
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+        let items: u64 = self.items.iter().map(load).sum();
+        let nanos: u64 = self.nanos.iter().map(load).sum();
+        format!(
+            "memory {} bytes (peak {}), balloon slots {}/{}, {} sallyport exits, {} items in {:?}",
+            load(&self.memory),
+            load(&self.memory_peak),
+            load(&self.balloon_slots),
+            load(&self.memory_slots),
+            load(&self.exits),
+            items,
+            Duration::from_nanos(nanos),
+        )
+    }
+}
+
+/// Serve the metrics as Prometheus text over HTTP on `addr`.
+pub fn serve(addr: &str) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on `{addr}`"))?;
+    log::info!("serving metrics on `{addr}`");
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(respond) {
+                log::warn!("failed to serve metrics: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    // Any request gets the metrics, so only skip its header.
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let body = METRICS.render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let metrics = Metrics::new();
+        metrics.map(4096);
+        metrics.balloon(8192);
+        metrics.set_memory_slots(32);
+        metrics.exit();
+        metrics.item(Kind::Syscall, Duration::from_millis(1500));
+
+        let text = metrics.render();
+        for line in [
+            "# TYPE enarx_keep_memory_bytes gauge",
+            "enarx_keep_memory_bytes 12288",
+            "enarx_keep_memory_peak_bytes 12288",
+            "enarx_keep_memory_slots 32",
+            "enarx_keep_balloon_slots 1",
+            "enarx_keep_sallyport_exits_total 1",
+            "enarx_keep_sallyport_items_total{kind=\"syscall\"} 1",
+            "enarx_keep_sallyport_items_total{kind=\"gdbcall\"} 0",
+            "enarx_keep_sallyport_seconds_total{kind=\"syscall\"} 1.5",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing `{line}` in:\n{text}"
+            );
+        }
+
+        assert_eq!(
+            metrics.summary(),
+            "memory 12288 bytes (peak 12288), balloon slots 1/32, 1 sallyport exits, 1 items in 1.5s"
+        );
+    }
+}
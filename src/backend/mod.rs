@@ -9,6 +9,7 @@ pub mod sev;
 #[cfg(enarx_with_shim)]
 pub mod sgx;
 
+pub mod metrics;
 pub mod nil;
 
 #[cfg(enarx_with_shim)]
@@ -17,11 +18,14 @@ mod binary;
 #[cfg(enarx_with_shim)]
 mod probe;
 
+#[cfg(enarx_with_shim)]
+pub use binary::check_shim;
 #[cfg(enarx_with_shim)]
 use binary::{Binary, Loader, Mapper};
 
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -54,11 +58,78 @@ impl DerefMut for Signatures {
     }
 }
 
+/// Identity of a Keep chosen by its author when signing it.
+///
+/// Values which are not set default to the ones in the shim.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Identity {
+    /// SGX ISV product ID
+    pub isv_prod_id: Option<u16>,
+
+    /// SGX ISV security version number
+    pub isv_svn: Option<u16>,
+
+    /// SEV-SNP guest policy
+    pub policy: Option<u64>,
+
+    /// SEV-SNP family ID
+    pub family_id: Option<[u8; 16]>,
+
+    /// SEV-SNP image ID
+    pub image_id: Option<[u8; 16]>,
+
+    /// SEV-SNP guest security version number
+    pub guest_svn: Option<u32>,
+}
+
+/// Maximum guest memory of the Keep run by this process in bytes, 0 if unlimited
+static MEMORY_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Limit the guest memory of the Keep run by this process to `limit` bytes.
+pub fn set_memory_limit(limit: Option<u64>) {
+    MEMORY_LIMIT.store(limit.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// The maximum guest memory of the Keep run by this process, if limited.
+pub fn memory_limit() -> Option<u64> {
+    match MEMORY_LIMIT.load(Ordering::Relaxed) {
+        0 => None,
+        limit => Some(limit),
+    }
+}
+
+/// Fail if the initial `memory` of a Keep in bytes exceeds its limit.
+#[cfg(enarx_with_shim)]
+fn check_memory_limit(memory: u64) -> Result<()> {
+    match memory_limit() {
+        Some(limit) if memory > limit => anyhow::bail!(
+            "Keep needs {memory} bytes of memory, more than its limit of {limit} bytes"
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Fail if the guest memory of the Keep is limited, which the backend called
+/// `name` cannot enforce.
+fn refuse_memory_limit(name: &str) -> Result<()> {
+    match memory_limit() {
+        Some(limit) => anyhow::bail!(
+            "The `{name}` backend cannot limit the memory of a Keep to {limit} bytes, unset `--memory` and the configured `memory`"
+        ),
+        None => Ok(()),
+    }
+}
+
 pub(crate) trait Config: Sized {
     type Flags;
 
     fn flags(flags: u32) -> Self::Flags;
-    fn new(shim: &Binary<'_>, exec: &Binary<'_>, signatures: Option<Signatures>) -> Result<Self>;
+    fn new(
+        shim: &Binary<'_>,
+        exec: &Binary<'_>,
+        signatures: Option<Signatures>,
+        identity: &Identity,
+    ) -> Result<Self>;
 }
 
 pub trait Backend: Sync + Send {
@@ -82,17 +153,17 @@ pub trait Backend: Sync + Send {
         signatures: Option<Signatures>,
     ) -> Result<Arc<dyn Keep>>;
 
-    /// Hash the inputs
-    fn hash(&self, shim: &[u8], exec: &[u8]) -> Result<Vec<u8>>;
+    /// Hash the inputs, returning the data to be signed for a Keep with `identity`
+    fn hash(&self, shim: &[u8], exec: &[u8], identity: &Identity) -> Result<Vec<u8>>;
 
     /// Whether or not the platform has support for this keep type
     fn have(&self) -> bool {
-        !self.data().iter().fold(false, |e, d| e | !d.pass)
+        !self.data().iter().any(Datum::fails)
     }
 
     /// Whether or not the machine is correctly configured for this keep type
     fn configured(&self) -> bool {
-        !self.config().iter().fold(false, |e, d| e | !d.pass)
+        !self.config().iter().any(Datum::fails)
     }
 
     #[cfg(windows)]
@@ -102,21 +173,78 @@ pub trait Backend: Sync + Send {
 
 impl Serialize for dyn Backend {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let mut backend = serializer.serialize_struct("Backend", 2)?;
+        let mut backend = serializer.serialize_struct("Backend", 3)?;
         backend.serialize_field("backend", self.name())?;
         backend.serialize_field("data", &self.data())?;
+        backend.serialize_field("config", &self.config())?;
         backend.end()
     }
 }
 
+/// Whether a backend requires a datum to pass or merely recommends it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// The backend does not work unless the datum passes.
+    Required,
+
+    /// The backend works, but is not fully ready unless the datum passes.
+    Recommended,
+}
+
+/// The kind of action that resolves a failing datum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Remediation {
+    /// The CPU or platform lacks support, other hardware is needed.
+    Hardware,
+
+    /// The feature needs to be enabled in the BIOS or the firmware updated.
+    Firmware,
+
+    /// The kernel, its command line or its module parameters need changing.
+    Kernel,
+
+    /// The user needs access to a device.
+    Permissions,
+
+    /// A resource limit of the user needs raising.
+    Limits,
+
+    /// A host service needs to be installed or started.
+    Service,
+}
+
+impl std::fmt::Display for Remediation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Hardware => "hardware",
+            Self::Firmware => "firmware",
+            Self::Kernel => "kernel",
+            Self::Permissions => "permissions",
+            Self::Limits => "limits",
+            Self::Service => "service",
+        })
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct Datum {
+    /// A stable identifier of this datum, e.g. `sev.driver`.
+    pub id: &'static str,
+
     /// The name of this datum.
     pub name: String,
 
     /// Whether the datum indicates support for the platform or not.
     pub pass: bool,
 
+    /// Whether the datum is required or recommended for the backend.
+    pub severity: Severity,
+
+    /// The kind of action that resolves a failure of this datum.
+    pub remediation: Remediation,
+
     /// Short additional information to display to the user.
     pub info: Option<String>,
 
@@ -124,6 +252,13 @@ pub struct Datum {
     pub mesg: Option<String>,
 }
 
+impl Datum {
+    /// Whether this datum is required and does not pass.
+    pub fn fails(&self) -> bool {
+        !self.pass && self.severity == Severity::Required
+    }
+}
+
 pub trait Keep {
     /// Creates a new thread in the keep.
     fn spawn(self: Arc<Self>) -> Result<Option<Box<dyn Thread>>>;
@@ -178,7 +313,7 @@ impl Backend for NotSupportedBackend {
         unimplemented!()
     }
 
-    fn hash(&self, _shim: &[u8], _exec: &[u8]) -> Result<Vec<u8>> {
+    fn hash(&self, _shim: &[u8], _exec: &[u8], _identity: &Identity) -> Result<Vec<u8>> {
         unimplemented!()
     }
 }
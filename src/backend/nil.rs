@@ -2,7 +2,7 @@
 
 use std::sync::{Arc, RwLock};
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::{bail, Result};
 #[cfg(windows)]
 use enarx_exec_wasmtime::Args;
@@ -58,6 +58,9 @@ impl crate::backend::Backend for Backend {
             bail!("The nil backend cannot be called with an executable!")
         }
 
+        // The workload runs in the memory of this process.
+        super::refuse_memory_limit(self.name())?;
+
         #[cfg(unix)]
         let thread = Thread;
 
@@ -70,7 +73,7 @@ impl crate::backend::Backend for Backend {
     }
 
     #[inline]
-    fn hash(&self, shim: &[u8], exec: &[u8]) -> Result<Vec<u8>> {
+    fn hash(&self, shim: &[u8], exec: &[u8], _identity: &Identity) -> Result<Vec<u8>> {
         if !shim.is_empty() {
             bail!("The nil backend cannot be called with shim!")
         }
@@ -79,6 +82,9 @@ impl crate::backend::Backend for Backend {
             bail!("The nil backend cannot be called with an executable!")
         }
 
+        // The workload runs in the memory of this process.
+        super::refuse_memory_limit(self.name())?;
+
         Ok(Vec::new())
     }
 
@@ -126,6 +132,9 @@ mod test {
         assert!(backend.shim().is_empty());
         assert!(backend.have());
         assert!(backend.data().is_empty());
-        assert!(backend.hash(&[], &[]).unwrap().is_empty());
+        assert!(backend
+            .hash(&[], &[], &Default::default())
+            .unwrap()
+            .is_empty());
     }
 }
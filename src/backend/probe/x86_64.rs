@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::Datum;
+use crate::backend::{Datum, Remediation, Severity};
 
 use static_assertions::const_assert_eq;
 
@@ -55,6 +55,7 @@ impl Vendor {
 }
 
 pub struct CpuId {
+    pub id: &'static str,
     pub name: &'static str,
     pub leaf: u32,
     pub subl: u32,
@@ -121,8 +122,11 @@ impl CpuId {
 impl From<&CpuId> for Datum {
     fn from(cpuid: &CpuId) -> Datum {
         let datum = Datum {
+            id: cpuid.id,
             name: cpuid.name.into(),
             pass: false,
+            severity: Severity::Required,
+            remediation: Remediation::Hardware,
             info: None,
             mesg: None,
         };
@@ -145,10 +149,9 @@ impl From<&CpuId> for Datum {
         };
 
         Datum {
-            name: datum.name,
             pass,
             info,
-            mesg: datum.mesg,
+            ..datum
         }
     }
 }
@@ -6,8 +6,11 @@ use super::snp::launch::*;
 use super::snp::ByteSized;
 
 use super::SnpKeepPersonality;
+use crate::backend::check_memory_limit;
 use crate::backend::kvm::builder::kvm_try_from_builder;
 use crate::backend::kvm::mem::Region;
+use crate::backend::kvm::KeepPersonality;
+use crate::backend::metrics::METRICS;
 
 use std::convert::TryFrom;
 use std::sync::{Arc, RwLock};
@@ -119,6 +122,7 @@ impl super::super::Mapper for Builder {
             to,
             with,
             self.regions.len() as _,
+            SnpKeepPersonality::mem_flags(),
         )?;
 
         let dp = VmplPerms::empty();
@@ -213,11 +217,7 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
                 .get("sev")
                 .context("Failed to get SEV signature.")?;
 
-            if sig_blob.len() != (core::mem::size_of::<IdAuth>()) {
-                return Err(anyhow!("Invalid SEV signature blob size."));
-            }
-
-            id_auth = unsafe { (sig_blob.as_ptr() as *const IdAuth).read_unaligned() };
+            id_auth = super::split_signature(sig_blob)?.1;
 
             Finish::new(Some((&id_block, &id_auth)), true, [0u8; 32])
         } else {
@@ -228,6 +228,11 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
             .finish(finish)
             .context("SNP Launcher finish failed")?;
 
+        let memory = regions.iter().map(|r| r.as_guest().count).sum();
+        check_memory_limit(memory)?;
+        METRICS.map(memory);
+        METRICS.set_memory_slots(kvm_fd.get_nr_memslots() as _);
+
         Ok(Arc::new(RwLock::new(super::Keep::<SnpKeepPersonality> {
             kvm_fd,
             vm_fd,
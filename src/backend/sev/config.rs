@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::snp::Parameters;
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::{anyhow, Result};
 use goblin::elf64::program_header::PT_LOAD;
 use sallyport::elf::{self, pf::kvm::SALLYPORT};
@@ -23,6 +23,7 @@ impl super::super::Config for Config {
         shim: &super::super::Binary<'_>,
         _exec: &super::super::Binary<'_>,
         signatures: Option<Signatures>,
+        identity: &Identity,
     ) -> Result<Self> {
         let sallyport_headers = shim.headers(PT_LOAD).filter(|p| p.p_flags & SALLYPORT != 0);
 
@@ -37,18 +38,30 @@ impl super::super::Config for Config {
 
         let parameters: Parameters = unsafe {
             Parameters {
-                policy: shim
-                    .note(elf::note::NAME, elf::note::snp::POLICY)
-                    .ok_or_else(|| anyhow!("KVM shim is missing POLICY"))?,
-                family_id: shim
-                    .note(elf::note::NAME, elf::note::snp::FAMILY_ID)
-                    .ok_or_else(|| anyhow!("KVM shim is missing FAMILY_ID"))?,
-                image_id: shim
-                    .note(elf::note::NAME, elf::note::snp::IMAGE_ID)
-                    .ok_or_else(|| anyhow!("KVM shim is missing IMAGE_ID"))?,
-                guest_svn: shim
-                    .note(elf::note::NAME, elf::note::snp::SVN)
-                    .ok_or_else(|| anyhow!("KVM shim is missing GUEST_SVN"))?,
+                policy: match identity.policy {
+                    Some(policy) => policy,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::snp::POLICY)
+                        .ok_or_else(|| anyhow!("KVM shim is missing POLICY"))?,
+                },
+                family_id: match identity.family_id {
+                    Some(family_id) => family_id,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::snp::FAMILY_ID)
+                        .ok_or_else(|| anyhow!("KVM shim is missing FAMILY_ID"))?,
+                },
+                image_id: match identity.image_id {
+                    Some(image_id) => image_id,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::snp::IMAGE_ID)
+                        .ok_or_else(|| anyhow!("KVM shim is missing IMAGE_ID"))?,
+                },
+                guest_svn: match identity.guest_svn {
+                    Some(guest_svn) => guest_svn,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::snp::SVN)
+                        .ok_or_else(|| anyhow!("KVM shim is missing GUEST_SVN"))?,
+                },
             }
         };
 
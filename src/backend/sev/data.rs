@@ -3,7 +3,7 @@
 pub use crate::backend::kvm::data::{dev_kvm, kvm_version};
 
 use crate::backend::probe::x86_64::{CpuId, Vendor};
-use crate::backend::Datum;
+use crate::backend::{Datum, Remediation, Severity};
 
 use std::arch::x86_64::__cpuid_count;
 use std::fs::OpenOptions;
@@ -49,8 +49,11 @@ pub fn has_reasonable_memlock_rlimit() -> Datum {
     };
 
     Datum {
+        id: "sev.memlock-rlimit",
         name: "MEMLOCK rlimit allows for".into(),
         pass,
+        severity: Severity::Required,
+        remediation: Remediation::Limits,
         info,
         mesg,
     }
@@ -58,37 +61,124 @@ pub fn has_reasonable_memlock_rlimit() -> Datum {
 
 pub fn dev_sev() -> Datum {
     Datum {
+        id: "sev.driver",
         name: "Driver".into(),
         pass: std::path::Path::new("/dev/sev").exists(),
+        severity: Severity::Required,
+        remediation: Remediation::Kernel,
         info: Some("/dev/sev".into()),
         mesg: None,
     }
 }
 
-pub fn sev_enabled_in_kernel() -> Datum {
-    let mut datum = Datum {
-        name: " SEV-SNP is enabled in host kernel".into(),
-        pass: false,
-        info: None,
-        mesg: None,
+/// Check that a boolean parameter of the `kvm_amd` module is enabled.
+fn kvm_amd_param(id: &'static str, name: &str, param: &str) -> Datum {
+    let path = format!("/sys/module/kvm_amd/parameters/{param}");
+    let value = std::fs::read_to_string(&path).ok();
+    let pass = matches!(value.as_deref().map(str::trim), Some("1" | "Y"));
+
+    let mesg = if !pass {
+        Some(format!(
+            "The `kvm_amd` module must be loaded with `{param}=1`, e.g. by passing \
+             `kvm_amd.{param}=1` on the kernel command line or with \
+             `options kvm_amd {param}=1` in `/etc/modprobe.d/`."
+        ))
+    } else {
+        None
     };
 
-    let mod_param = "/sys/module/kvm_amd/parameters/sev_snp";
-    if std::path::Path::new(mod_param).exists() {
-        if let Ok(val) = std::fs::read_to_string(mod_param) {
-            datum.pass = val.trim() == "1" || val.trim() == "Y";
-        }
+    Datum {
+        id,
+        name: name.into(),
+        pass,
+        severity: Severity::Required,
+        remediation: Remediation::Kernel,
+        info: Some(path),
+        mesg,
     }
+}
+
+pub fn sev_enabled_in_kernel() -> Datum {
+    kvm_amd_param("sev.kernel.sev", " SEV is enabled in host kernel", "sev")
+}
+
+pub fn sev_es_enabled_in_kernel() -> Datum {
+    kvm_amd_param(
+        "sev.kernel.sev-es",
+        " SEV-ES is enabled in host kernel",
+        "sev_es",
+    )
+}
+
+pub fn sev_snp_enabled_in_kernel() -> Datum {
+    kvm_amd_param(
+        "sev.kernel.sev-snp",
+        " SEV-SNP is enabled in host kernel",
+        "sev_snp",
+    )
+}
+
+/// Find the kernel command line parameters that disable SEV-SNP or the IOMMU it relies on.
+fn disabling_params(cmdline: &str) -> Vec<&str> {
+    const PARAMS: &[&str] = &[
+        "mem_encrypt",
+        "kvm_amd.sev",
+        "kvm_amd.sev_es",
+        "kvm_amd.sev_snp",
+        "amd_iommu",
+        "iommu",
+    ];
+
+    cmdline
+        .split_whitespace()
+        .filter(|arg| match arg.split_once('=') {
+            Some((key, value)) => {
+                PARAMS.contains(&key)
+                    && matches!(value.to_lowercase().as_str(), "0" | "n" | "no" | "off")
+            }
+            None => false,
+        })
+        .collect()
+}
+
+pub fn kernel_cmdline() -> Datum {
+    let (pass, info) = match std::fs::read_to_string("/proc/cmdline") {
+        Ok(cmdline) => match disabling_params(&cmdline) {
+            disabling if disabling.is_empty() => (true, None),
+            disabling => (false, Some(disabling.join(" "))),
+        },
+        Err(_) => (false, Some("failed to read /proc/cmdline".into())),
+    };
+
+    let mesg = if !pass {
+        let mesg = "The kernel command line should not disable memory encryption, \
+                    SEV or the IOMMU, e.g. with `mem_encrypt=off`, `kvm_amd.sev_snp=0` \
+                    or `amd_iommu=off`.";
+        Some(mesg.into())
+    } else {
+        None
+    };
 
-    datum
+    Datum {
+        id: "sev.kernel.cmdline",
+        name: " Kernel command line does not disable SEV-SNP".into(),
+        pass,
+        severity: Severity::Recommended,
+        remediation: Remediation::Kernel,
+        info,
+        mesg,
+    }
 }
 
 pub fn dev_sev_readable() -> Datum {
     let opts = OpenOptions::new().read(true).open("/dev/sev");
 
     Datum {
+        id: "sev.driver.readable",
         name: " /dev/sev is readable by user".into(),
         pass: opts.is_ok(),
+        severity: Severity::Required,
+        remediation: Remediation::Permissions,
         info: None,
         mesg: None,
     }
@@ -98,8 +188,11 @@ pub fn dev_sev_writable() -> Datum {
     let opts = OpenOptions::new().write(true).open("/dev/sev");
 
     Datum {
+        id: "sev.driver.writable",
         name: " /dev/sev is writable by user".into(),
         pass: opts.is_ok(),
+        severity: Severity::Required,
+        remediation: Remediation::Permissions,
         info: None,
         mesg: None,
     }
@@ -107,6 +200,7 @@ pub fn dev_sev_writable() -> Datum {
 
 pub const CPUIDS: &[CpuId] = &[
     CpuId {
+        id: "sev.cpu",
         name: "CPU",
         leaf: 0x80000000,
         subl: 0x00000000,
@@ -114,6 +208,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: None,
     },
     CpuId {
+        id: "sev.cpu.epyc",
         name: " Microcode support",
         leaf: 0x80000002,
         subl: 0x00000000,
@@ -136,6 +231,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.sme",
         name: " Secure Memory Encryption (SME)",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -143,6 +239,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.phys-addr-reduction",
         name: "  Physical address bit reduction",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -153,6 +250,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.c-bit",
         name: "  C-bit location in page table entry",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -163,6 +261,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.sev",
         name: " Secure Encrypted Virtualization (SEV)",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -170,6 +269,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.guests",
         name: "  Number of encrypted guests supported simultaneously",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -177,6 +277,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.min-asid",
         name: "  Minimum ASID value for SEV-enabled, SEV-ES disabled guest",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -184,6 +285,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.snp",
         name: " Secure Encrypted Virtualization Secure Nested Paging (SEV-SNP)",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -191,6 +293,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
     CpuId {
+        id: "sev.cpu.page-flush-msr",
         name: " Page Flush MSR available",
         leaf: 0x8000001f,
         subl: 0x00000000,
@@ -198,3 +301,17 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Amd),
     },
 ];
+
+#[cfg(test)]
+mod test {
+    use super::disabling_params;
+
+    #[test]
+    fn cmdline() {
+        assert!(disabling_params("BOOT_IMAGE=/vmlinuz ro quiet kvm_amd.sev_snp=1").is_empty());
+        assert_eq!(
+            disabling_params("ro mem_encrypt=off kvm_amd.sev=N amd_iommu=on iommu=Off"),
+            vec!["mem_encrypt=off", "kvm_amd.sev=N", "iommu=Off"]
+        );
+    }
+}
@@ -10,20 +10,24 @@ mod cpuid_page;
 mod data;
 mod hasher;
 
+use snp::launch::{IdAuth, IdBlock};
 use snp::vcek::get_vcek_reader;
+use snp::ByteSized;
 
 use super::kvm::mem::Region;
 use super::kvm::{Keep, KeepPersonality};
 use super::Loader;
 use data::{
     dev_kvm, dev_sev, dev_sev_readable, dev_sev_writable, has_reasonable_memlock_rlimit,
-    kvm_version, sev_enabled_in_kernel, CPUIDS,
+    kernel_cmdline, kvm_version, sev_enabled_in_kernel, sev_es_enabled_in_kernel,
+    sev_snp_enabled_in_kernel, CPUIDS,
 };
 
 use std::io;
+use std::mem::size_of;
 use std::sync::Arc;
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::{bail, Context, Result};
 use kvm_bindings::bindings::kvm_enc_region;
 use kvm_ioctls::VmFd;
@@ -96,7 +100,14 @@ impl super::Backend for Backend {
     }
 
     fn data(&self) -> Vec<super::Datum> {
-        let mut data = vec![dev_sev(), sev_enabled_in_kernel(), dev_kvm(), kvm_version()];
+        let mut data = vec![
+            dev_sev(),
+            sev_enabled_in_kernel(),
+            sev_es_enabled_in_kernel(),
+            sev_snp_enabled_in_kernel(),
+            dev_kvm(),
+            kvm_version(),
+        ];
         data.extend(CPUIDS.iter().map(|c| c.into()));
         data
     }
@@ -106,6 +117,7 @@ impl super::Backend for Backend {
             dev_sev_readable(),
             dev_sev_writable(),
             has_reasonable_memlock_rlimit(),
+            kernel_cmdline(),
         ]
     }
 
@@ -116,11 +128,42 @@ impl super::Backend for Backend {
         exec: &[u8],
         signatures: Option<Signatures>,
     ) -> Result<Arc<dyn super::Keep>> {
-        builder::Builder::load(shim, exec, signatures)
+        // Launch the Keep with the identity it was signed for.
+        let identity = match signatures.as_ref().and_then(|s| s.get("sev")) {
+            Some(blob) => match split_signature(blob)?.0 {
+                Some(id_block) => Identity {
+                    policy: Some(id_block.policy),
+                    family_id: Some(id_block.family_id),
+                    image_id: Some(id_block.image_id),
+                    guest_svn: Some(id_block.guest_svn),
+                    ..Default::default()
+                },
+                None => Identity::default(),
+            },
+            None => Identity::default(),
+        };
+        builder::Builder::load(shim, exec, signatures, &identity)
     }
 
     #[inline]
-    fn hash(&self, shim: &[u8], exec: &[u8]) -> Result<Vec<u8>> {
-        hasher::Hasher::load(shim, exec, None)
+    fn hash(&self, shim: &[u8], exec: &[u8], identity: &Identity) -> Result<Vec<u8>> {
+        hasher::Hasher::load(shim, exec, None, identity)
     }
 }
+
+/// Split a SEV signature into the signed `ID_BLOCK`, if present, and the `ID_AUTH` block.
+///
+/// Signatures created by `enarx key sign` are prefixed with the `ID_BLOCK`, which
+/// holds the policy, family ID, image ID and guest SVN chosen when signing.
+pub(crate) fn split_signature(blob: &[u8]) -> Result<(Option<IdBlock>, IdAuth)> {
+    let (id_block, id_auth) = match blob.len() {
+        n if n == size_of::<IdAuth>() => (None, blob),
+        n if n == size_of::<IdBlock>() + size_of::<IdAuth>() => {
+            let (id_block, id_auth) = blob.split_at(size_of::<IdBlock>());
+            (unsafe { IdBlock::from_bytes(id_block) }, id_auth)
+        }
+        _ => bail!("Invalid SEV signature blob size."),
+    };
+    let id_auth = unsafe { IdAuth::from_bytes(id_auth) }.context("Invalid SEV signature blob")?;
+    Ok((id_block, id_auth))
+}
@@ -2,6 +2,7 @@
 
 use super::config::Config;
 use super::ioctls::*;
+use crate::backend::metrics::METRICS;
 
 use std::convert::TryFrom;
 use std::fs::{File, OpenOptions};
@@ -209,6 +210,8 @@ impl TryFrom<Builder> for Arc<dyn super::super::Keep> {
             });
         }
 
+        METRICS.map(builder.mmap.size() as _);
+
         Ok(Arc::new(super::Keep {
             sallyport_block_size: builder.cnfg.sallyport_block_size,
             mem: builder.mmap,
@@ -2,7 +2,7 @@
 
 use std::num::NonZeroU32;
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use anyhow::{anyhow, Result};
 use goblin::elf::program_header::{PF_R, PF_W, PF_X};
 use sallyport::elf;
@@ -51,6 +51,7 @@ impl super::super::Config for Config {
         shim: &super::super::Binary<'_>,
         _exec: &super::super::Binary<'_>,
         signatures: Option<Signatures>,
+        identity: &Identity,
     ) -> Result<Self> {
         unsafe {
             let params: Parameters = Parameters {
@@ -70,12 +71,18 @@ impl super::super::Config for Config {
                         .note(elf::note::NAME, elf::note::sgx::ATTRMASK)
                         .ok_or_else(|| anyhow!("SGX shim is missing ATTRMASK"))?,
                 },
-                pid: shim
-                    .note(elf::note::NAME, elf::note::sgx::PID)
-                    .ok_or_else(|| anyhow!("SGX shim is missing PID"))?,
-                svn: shim
-                    .note(elf::note::NAME, elf::note::sgx::SVN)
-                    .ok_or_else(|| anyhow!("SGX shim is missing SVN"))?,
+                pid: match identity.isv_prod_id {
+                    Some(pid) => pid,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::sgx::PID)
+                        .ok_or_else(|| anyhow!("SGX shim is missing PID"))?,
+                },
+                svn: match identity.isv_svn {
+                    Some(svn) => svn,
+                    None => shim
+                        .note(elf::note::NAME, elf::note::sgx::SVN)
+                        .ok_or_else(|| anyhow!("SGX shim is missing SVN"))?,
+                },
             };
 
             let ssap: u8 = shim
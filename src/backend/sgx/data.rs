@@ -2,7 +2,7 @@
 
 use crate::backend::probe::x86_64::{CpuId, Vendor};
 use crate::backend::sgx::AESM_SOCKET;
-use crate::backend::Datum;
+use crate::backend::{Datum, Remediation, Severity};
 
 use sgx::parameters::{Features, MiscSelect, Xfrm};
 
@@ -36,6 +36,7 @@ fn humanize(mut size: f64) -> (f64, &'static str) {
 
 pub const CPUIDS: &[CpuId] = &[
     CpuId {
+        id: "sgx.cpu",
         name: "CPU",
         leaf: 0x80000000,
         subl: 0x00000000,
@@ -43,6 +44,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: None,
     },
     CpuId {
+        id: "sgx.cpu.sgx",
         name: " SGX Support",
         leaf: 0x00000007,
         subl: 0x00000000,
@@ -50,6 +52,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.sgx1",
         name: "  Version 1",
         leaf: 0x00000012,
         subl: 0x00000000,
@@ -57,6 +60,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.sgx2",
         name: "  Version 2",
         leaf: 0x00000012,
         subl: 0x00000000,
@@ -64,6 +68,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.flc",
         name: "  FLC Support",
         leaf: 0x00000007,
         subl: 0x00000000,
@@ -71,6 +76,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.max-size-32",
         name: "  Max Size (32-bit)",
         leaf: 0x00000012,
         subl: 0x00000000,
@@ -82,6 +88,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.max-size-64",
         name: "  Max Size (64-bit)",
         leaf: 0x00000012,
         subl: 0x00000000,
@@ -93,6 +100,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.miscselect",
         name: "  MiscSelect",
         leaf: 0x00000012,
         subl: 0x00000000,
@@ -103,6 +111,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.features",
         name: "  Features",
         leaf: 0x00000012,
         subl: 0x00000001,
@@ -113,6 +122,7 @@ pub const CPUIDS: &[CpuId] = &[
         vend: Some(Vendor::Intel),
     },
     CpuId {
+        id: "sgx.cpu.xfrm",
         name: "  Xfrm",
         leaf: 0x00000012,
         subl: 0x00000001,
@@ -148,17 +158,23 @@ pub fn epc_size(max: u32) -> Datum {
     }
 
     Datum {
+        id: "sgx.epc-size",
         name: "  EPC Size".into(),
         mesg: None,
         pass,
+        severity: Severity::Required,
+        remediation: Remediation::Firmware,
         info,
     }
 }
 
 pub fn dev_sgx_enclave() -> Datum {
     Datum {
+        id: "sgx.driver",
         name: "Driver".into(),
         pass: File::open("/dev/sgx_enclave").is_ok(),
+        severity: Severity::Required,
+        remediation: Remediation::Kernel,
         info: Some("/dev/sgx_enclave".into()),
         mesg: None,
     }
@@ -166,8 +182,11 @@ pub fn dev_sgx_enclave() -> Datum {
 
 pub fn aesm_socket() -> Datum {
     Datum {
+        id: "sgx.aesm",
         name: "AESM Daemon Socket".into(),
         pass: cfg!(feature = "disable-sgx-attestation") || Path::new(AESM_SOCKET).exists(),
+        severity: Severity::Required,
+        remediation: Remediation::Service,
         info: Some(AESM_SOCKET.into()),
         mesg: None,
     }
@@ -13,7 +13,7 @@ use super::Loader;
 use anyhow::Result;
 use mmarinus::{perms, Map};
 
-use crate::backend::Signatures;
+use crate::backend::{Identity, Signatures};
 use std::arch::x86_64::__cpuid_count;
 use std::fs::File;
 use std::sync::{Arc, RwLock};
@@ -69,12 +69,14 @@ impl crate::backend::Backend for Backend {
         exec: &[u8],
         signatures: Option<Signatures>,
     ) -> Result<Arc<dyn super::Keep>> {
-        builder::Builder::load(shim, exec, signatures)
+        // The pages added on demand are not accounted for.
+        super::refuse_memory_limit(self.name())?;
+        builder::Builder::load(shim, exec, signatures, &Identity::default())
     }
 
     #[inline]
-    fn hash(&self, shim: &[u8], exec: &[u8]) -> Result<Vec<u8>> {
-        hasher::Hasher::load(shim, exec, None)
+    fn hash(&self, shim: &[u8], exec: &[u8], identity: &Identity) -> Result<Vec<u8>> {
+        hasher::Hasher::load(shim, exec, None, identity)
     }
 }
 
@@ -3,6 +3,7 @@
 use super::attestation::{get_attestation_key_id, get_key_size, get_quote, get_target_info};
 #[cfg(feature = "gdb")]
 use crate::backend::execute_gdb;
+use crate::backend::metrics::{Kind, METRICS};
 use crate::backend::sgx::attestation::get_quote_size;
 use crate::backend::sgx::ioctls::*;
 use crate::backend::Command;
@@ -15,9 +16,10 @@ use std::mem::{forget, size_of, MaybeUninit};
 #[cfg(feature = "gdb")]
 use std::net::TcpStream;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
-use libc::{EINVAL, PROT_READ};
+use libc::EINVAL;
 use mmarinus::{perms, Map, Shared};
 use sallyport::host::{deref_aligned, deref_slice};
 use sallyport::item;
@@ -225,9 +227,9 @@ fn sgx_enarxcall<'a>(
                 return Ok(None);
             }
 
-            // TODO: https://github.com/enarx/enarx/issues/1892
-            let mut parameters =
-                RestrictPermissions::new(*addr - keep.mem.addr(), *len, PROT_READ as _);
+            // The shim accepts the intersection of the current and the
+            // requested permissions, and extends the missing ones itself.
+            let mut parameters = RestrictPermissions::new(*addr - keep.mem.addr(), *len, *prot);
             ENCLAVE_RESTRICT_PERMISSIONS
                 .ioctl(&mut keep.enclave.try_clone().unwrap(), &mut parameters)
                 .context("ENCLAVE_RESTRICT_PERMISSIONS failed")?;
@@ -359,7 +361,9 @@ impl super::super::Thread for Thread {
         if self.cssa > 0 {
             if let (EENTER, ERESUME) = (how, self.how) {
                 let block: Block = self.block.as_mut_slice().into();
+                METRICS.exit();
                 for item in block {
+                    let (kind, start) = (Kind::from(&item), Instant::now());
                     match item {
                         Item::Gdbcall(_gdbcall, _data) => {
                             #[cfg(feature = "gdb")]
@@ -414,6 +418,7 @@ impl super::super::Thread for Thread {
                                 .context("sallyport::host::execute")?;
                         }
                     }
+                    METRICS.item(kind, start.elapsed());
                 }
             }
         }
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod verify;
+
+use clap::Subcommand;
+
+/// Commands for working with the attestation evidence of Enarx Keeps.
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    Verify(verify::Options),
+}
+
+impl Subcommands {
+    pub fn dispatch(self) -> anyhow::Result<()> {
+        match self {
+            Self::Verify(cmd) => cmd.execute(),
+        }
+    }
+}
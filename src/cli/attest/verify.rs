@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fs;
+
+use anyhow::{bail, Context};
+use camino::Utf8PathBuf;
+use clap::Args;
+use enarx_verifier::Policy;
+use pkcs8::der::Document;
+
+/// Verify the attestation evidence of a Keep.
+///
+/// The evidence is read from a certification request or certificate of the
+/// Keep and verified against a certificate chain read from a file, so that
+/// no network access is needed. For SNP Keeps the chain contains the ASK and
+/// ARK, and for SGX Keeps the PCK platform or processor CA and the root CA.
+///
+/// The report data of the evidence must be the hash of the public key of the
+/// request, and the claims of the Keep must satisfy the policy.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// PEM or DER encoded certification request or certificate of the Keep
+    #[clap(value_name = "FILE")]
+    input: Utf8PathBuf,
+
+    /// PEM file with the certificate chain, ending with the trust anchor
+    #[clap(long, value_name = "FILE")]
+    chain: Utf8PathBuf,
+
+    /// TOML policy of expected measurements, minimum TCB and SVN versions,
+    /// and whether to accept debug Keeps
+    #[clap(long, value_name = "FILE")]
+    policy: Option<Utf8PathBuf>,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let input = &self.input;
+        let buf = fs::read(input).with_context(|| format!("Failed to read `{input}`"))?;
+        let doc = std::str::from_utf8(&buf)
+            .ok()
+            .and_then(|pem| Document::from_pem(pem).ok())
+            .map(|(_, doc)| doc);
+        let der = match doc {
+            Some(doc) => doc.as_bytes().to_vec(),
+            None => buf,
+        };
+
+        let path = &self.chain;
+        let pem = fs::read(path).with_context(|| format!("Failed to read `{path}`"))?;
+        let chain = rustls_pemfile::certs(&mut pem.as_slice())
+            .with_context(|| format!("Failed to decode the certificate chain `{path}`"))?;
+        if chain.is_empty() {
+            bail!("No certificates found in `{path}`");
+        }
+
+        let policy = match self.policy {
+            Some(ref path) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read policy `{path}`"))?
+                .parse::<Policy>()
+                .with_context(|| format!("Failed to parse policy `{path}`"))?,
+            None => Policy::default(),
+        };
+
+        let claims = enarx_verifier::verify(&der, &chain, &policy)
+            .with_context(|| format!("Failed to verify `{input}`"))?;
+        print!("{claims}");
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod init;
+mod show;
 
 use clap::Subcommand;
 
@@ -8,12 +9,14 @@ use clap::Subcommand;
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     Init(init::Options),
+    Show(show::Options),
 }
 
 impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<()> {
         match self {
             Self::Init(cmd) => cmd.execute(),
+            Self::Show(cmd) => cmd.execute(),
         }
     }
 }
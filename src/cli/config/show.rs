@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::host_config::HostConfig;
+
+use clap::Args;
+
+/// Show the effective host configuration and where each value is set.
+///
+/// Values are read from environment variables, the user configuration file,
+/// e.g. `~/.config/enarx/config.toml`, and `/etc/enarx/config.toml`, in that
+/// order of precedence. Command-line options take precedence over all of them.
+#[derive(Args, Debug)]
+pub struct Options;
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        for setting in HostConfig::get().settings() {
+            println!("{} = {} # {}", setting.name, setting.value, setting.source);
+        }
+        Ok(())
+    }
+}
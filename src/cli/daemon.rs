@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::keep::{KeepInfo, KeepStatus, Request, Response, SocketOptions};
+use super::logging::{new_keep_id, KEEP_ID_ENV};
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, bail, Context};
+use camino::Utf8PathBuf;
+use clap::Args;
+use log::{error, info};
+
+/// Run a daemon serving multiple Keeps.
+///
+/// The daemon listens on a Unix socket for JSON requests to launch, list and
+/// stop Keeps and to fetch their logs and exit status, as sent by the
+/// `enarx keep` commands. Each Keep runs in its own `enarx run` or
+/// `enarx deploy` child process, whose output is written to a log file.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    socket: SocketOptions,
+
+    /// Directory of the Keep log files, by default `$XDG_STATE_HOME/enarx/keeps`
+    #[clap(long, env = "ENARX_DAEMON_STATE_DIR", value_name = "DIR")]
+    state_dir: Option<Utf8PathBuf>,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let state_dir = match self.state_dir {
+            Some(dir) => dir.into(),
+            None => dirs::state_dir()
+                .ok_or_else(|| anyhow!("Failed to find the state directory, pass `--state-dir`"))?
+                .join("enarx")
+                .join("keeps"),
+        };
+        let exe = std::env::current_exe().context("Failed to find the enarx executable")?;
+        let daemon = Arc::new(Daemon::new(exe, state_dir)?);
+
+        let listener = bind(&self.socket.path())?;
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Failed to accept connection: {e}");
+                    continue;
+                }
+            };
+            let daemon = daemon.clone();
+            thread::spawn(move || {
+                if let Err(e) = daemon.handle(stream) {
+                    error!("Failed to handle request: {e:#}");
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Bind the socket at `path`, only accessible by the owner.
+fn bind(path: &Path) -> anyhow::Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("Failed to create directory `{}`", dir.display()))?;
+    }
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!(
+                "An enarx daemon is already listening on `{}`",
+                path.display()
+            )
+        }
+        // Remove the socket left behind by a previous daemon.
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket `{}`", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on `{}`", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to set permissions of `{}`", path.display()))?;
+    info!("listening on `{}`", path.display());
+    Ok(listener)
+}
+
+struct Entry {
+    info: KeepInfo,
+    log: PathBuf,
+}
+
+type Keeps = Arc<Mutex<BTreeMap<String, Entry>>>;
+
+/// The Keeps run by the daemon.
+struct Daemon {
+    exe: PathBuf,
+    dir: PathBuf,
+    keeps: Keeps,
+}
+
+impl Daemon {
+    fn new(exe: PathBuf, dir: PathBuf) -> anyhow::Result<Self> {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .with_context(|| format!("Failed to create directory `{}`", dir.display()))?;
+        Ok(Self {
+            exe,
+            dir,
+            keeps: Default::default(),
+        })
+    }
+
+    /// Read a request from `stream` and write back the response.
+    fn handle(&self, stream: UnixStream) -> anyhow::Result<()> {
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .context("Failed to read request")?;
+        let response = serde_json::from_str(&line)
+            .context("Failed to parse request")
+            .and_then(|request| self.respond(request))
+            .unwrap_or_else(|e| Response::Error {
+                message: format!("{e:#}"),
+            });
+
+        let mut stream = &stream;
+        serde_json::to_writer(&mut stream, &response).context("Failed to write response")?;
+        stream.write_all(b"\n").context("Failed to write response")
+    }
+
+    fn respond(&self, request: Request) -> anyhow::Result<Response> {
+        let mut keeps = self.keeps.lock().unwrap();
+
+        let mut get = |id: &str| {
+            keeps
+                .get_mut(id)
+                .ok_or_else(|| anyhow!("No Keep with ID `{}`", id))
+        };
+
+        match request {
+            Request::Launch { args, cwd } => {
+                match args.first().map(String::as_str) {
+                    Some("run" | "deploy") => {}
+                    _ => bail!("Keeps must be launched with `run` or `deploy`"),
+                }
+
+                let id = new_keep_id();
+                let log = self.dir.join(format!("{id}.log"));
+                let file = File::create(&log)
+                    .with_context(|| format!("Failed to create log file `{}`", log.display()))?;
+
+                // The child gets fresh standard streams, so its exec socket
+                // lands on the first free file descriptor, i.e. FD 3.
+                let child = Command::new(&self.exe)
+                    .args(&args)
+                    .current_dir(&cwd)
+                    .env(KEEP_ID_ENV, &id)
+                    .stdin(Stdio::null())
+                    .stdout(file.try_clone()?)
+                    .stderr(file)
+                    .spawn()
+                    .with_context(|| format!("Failed to launch Keep in `{}`", cwd.display()))?;
+
+                info!(
+                    "keep {id} launched with pid {}: {}",
+                    child.id(),
+                    args.join(" ")
+                );
+                let info = KeepInfo {
+                    id: id.clone(),
+                    pid: child.id(),
+                    args,
+                    status: KeepStatus::Running,
+                };
+                keeps.insert(id.clone(), Entry { info, log });
+
+                let keeps = self.keeps.clone();
+                let watched = id.clone();
+                thread::spawn(move || watch(keeps, watched, child));
+                Ok(Response::Launched { id })
+            }
+
+            Request::List => Ok(Response::List {
+                keeps: keeps.values().map(|entry| entry.info.clone()).collect(),
+            }),
+
+            Request::Stop { id } => {
+                let entry = get(&id)?;
+                if entry.info.status != KeepStatus::Running {
+                    bail!("Keep `{}` is not running", id)
+                }
+                if unsafe { libc::kill(entry.info.pid as _, libc::SIGTERM) } != 0 {
+                    return Err(std::io::Error::last_os_error())
+                        .with_context(|| format!("Failed to stop Keep `{id}`"));
+                }
+                info!("keep {id} stopped");
+                Ok(Response::Stopped { id })
+            }
+
+            Request::Logs { id } => {
+                let entry = get(&id)?;
+                let logs = fs::read(&entry.log).with_context(|| {
+                    format!("Failed to read log file `{}`", entry.log.display())
+                })?;
+                Ok(Response::Logs {
+                    keep: entry.info.clone(),
+                    logs: String::from_utf8_lossy(&logs).into_owned(),
+                })
+            }
+        }
+    }
+}
+
+/// Wait for the Keep `id` to terminate and record its exit status.
+///
+/// The child is only reaped with the Keeps locked, once its status is recorded,
+/// so that a stopped Keep cannot have its PID reused by another process.
+fn watch(keeps: Keeps, id: String, mut child: Child) {
+    let mut siginfo = unsafe { std::mem::zeroed::<libc::siginfo_t>() };
+    while unsafe {
+        libc::waitid(
+            libc::P_PID,
+            child.id(),
+            &mut siginfo,
+            libc::WEXITED | libc::WNOWAIT,
+        )
+    } != 0
+    {
+        let e = std::io::Error::last_os_error();
+        if e.kind() != std::io::ErrorKind::Interrupted {
+            error!("Failed to wait for keep {id}: {e}");
+            break;
+        }
+    }
+
+    let mut keeps = keeps.lock().unwrap();
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(e) => {
+            error!("Failed to wait for keep {id}: {e}");
+            return;
+        }
+    };
+    if let Some(entry) = keeps.get_mut(&id) {
+        entry.info.status = match (status.code(), status.signal()) {
+            (Some(code), _) => KeepStatus::Exited { code },
+            (None, signal) => KeepStatus::Killed {
+                signal: signal.unwrap_or_default(),
+            },
+        };
+        info!("keep {id} {}", entry.info.status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps() {
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = Daemon::new("/bin/echo".into(), dir.path().into()).unwrap();
+
+        let err = daemon
+            .respond(Request::Launch {
+                args: vec!["key".into()],
+                cwd: dir.path().into(),
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Keeps must be launched with `run` or `deploy`"
+        );
+
+        let id = match daemon.respond(Request::Launch {
+            args: vec!["run".into(), "app.wasm".into()],
+            cwd: dir.path().into(),
+        }) {
+            Ok(Response::Launched { id }) => id,
+            response => panic!("unexpected response: {:?}", response),
+        };
+
+        // Wait for the Keep to terminate.
+        let pid = daemon.keeps.lock().unwrap()[&id].info.pid;
+        let keep = loop {
+            match daemon.respond(Request::Logs { id: id.clone() }).unwrap() {
+                Response::Logs { keep, logs } if keep.status != KeepStatus::Running => {
+                    assert_eq!(logs, "run app.wasm\n");
+                    break keep;
+                }
+                Response::Logs { .. } => thread::sleep(std::time::Duration::from_millis(10)),
+                response => panic!("unexpected response: {:?}", response),
+            }
+        };
+        assert_eq!(keep.pid, pid);
+
+        // The Keep has been reaped without another request.
+        assert_eq!(
+            unsafe { libc::waitpid(pid as _, std::ptr::null_mut(), libc::WNOHANG) },
+            -1
+        );
+        assert_eq!(keep.status, KeepStatus::Exited { code: 0 });
+
+        match daemon.respond(Request::List).unwrap() {
+            Response::List { keeps } => assert_eq!(keeps, vec![keep]),
+            response => panic!("unexpected response: {:?}", response),
+        }
+        assert!(daemon.respond(Request::Stop { id }).is_err());
+    }
+}
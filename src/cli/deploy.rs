@@ -1,13 +1,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::cli::{get_signatures, BackendOptions};
-use crate::drawbridge::parse_tag;
-use crate::exec::{open_package, run_package, EXECS};
+use crate::cli::{get_memory, get_signatures, load_exec, logging, parse_memory, BackendOptions};
+use crate::drawbridge::{parse_tag, public_client, verify_entry, TagSpec, VerifyingKey};
+use crate::exec::{open_package, run_package, RunOptions};
+use crate::host_config::HostConfig;
 
 use std::fmt::Debug;
 use std::fs;
 #[cfg(unix)]
 use std::os::unix::io::IntoRawFd;
+use std::time::Duration;
 
 use anyhow::{anyhow, bail, Context};
 use camino::Utf8PathBuf;
@@ -21,14 +23,63 @@ pub struct Options {
     #[clap(flatten)]
     pub backend: BackendOptions,
 
-    /// URL of the package to run.
+    /// Package to run, e.g. `user/repo:tag` or `host/user/repo:tag`.
+    ///
+    /// The package host is contacted over HTTPS, unless the package is prefixed
+    /// by an explicit scheme, e.g. `http://localhost:8080/user/repo:tag`.
+    /// Plain HTTP is refused unless `--insecure` is passed or the host
+    /// configuration allows it.
     #[clap(value_name = "PACKAGE")]
     pub package: String,
 
+    /// Path of a PEM-encoded bundle of additional CA certificates to trust
+    /// when fetching the package and its signatures.
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    pub ca_bundle: Option<Utf8PathBuf>,
+
     /// Path of the signature file to use.
     #[clap(long, value_name = "SIGNATURES")]
     pub signatures: Option<Utf8PathBuf>,
 
+    /// Path of an external exec binary to use instead of the builtin one
+    #[clap(long, env = "ENARX_EXEC", value_name = "EXEC")]
+    pub exec: Option<Utf8PathBuf>,
+
+    /// PEM-encoded public key of a trusted package publisher.
+    ///
+    /// If passed, the package must be signed by one of the given keys,
+    /// otherwise no Keep is created, and the Keep refuses to run a package
+    /// which does not match the signed digest. May be passed multiple times.
+    /// Only supported for packages fetched over HTTP(S).
+    #[clap(long, value_name = "PUBLIC_KEY")]
+    pub require_signer: Vec<Utf8PathBuf>,
+
+    /// Allow fetching the package over plain HTTP and deploying it to a backend
+    /// without hardware isolation, such as `nil` or `kvm`, where sealing keys
+    /// are derived from a public value.
+    #[clap(long)]
+    pub insecure: bool,
+
+    /// Seconds to wait for the workload to exit after `SIGTERM` or `SIGINT`,
+    /// before the Keep is killed
+    #[clap(
+        long,
+        env = "ENARX_SHUTDOWN_GRACE",
+        value_name = "SECONDS",
+        default_value = "30"
+    )]
+    pub shutdown_grace: u64,
+
+    /// Address to serve the metrics of the Keep on as Prometheus text,
+    /// e.g. `localhost:9100`
+    #[clap(long, env = "ENARX_METRICS_LISTEN", value_name = "ADDR")]
+    pub metrics_listen: Option<String>,
+
+    /// Maximum memory of the Keep, e.g. `2G`, by default the configured one
+    /// or else unlimited. Only supported by the `kvm` and `sev` backends.
+    #[clap(long, env = "ENARX_MEMORY", value_name = "SIZE", parse(try_from_str = parse_memory))]
+    pub memory: Option<u64>,
+
     /// gdb options
     #[cfg(feature = "gdb")]
     #[clap(long, default_value = "localhost:23456")]
@@ -38,21 +89,75 @@ pub struct Options {
 impl Options {
     pub fn execute(self) -> anyhow::Result<()> {
         let Self {
-            backend,
+            backend: backend_options,
             package,
+            ca_bundle,
             signatures,
+            exec,
+            require_signer,
+            insecure,
+            shutdown_grace,
+            metrics_listen,
+            memory,
             #[cfg(feature = "gdb")]
             gdblisten,
         } = self;
+        let shutdown_grace = Duration::from_secs(shutdown_grace);
+        let memory = get_memory(memory)?;
+
+        let (scheme, spec) = package
+            .split_once("://")
+            .unwrap_or(("https", package.as_str()));
+        let remote = matches!(scheme, "http" | "https");
+
+        let policy = HostConfig::get();
+        if scheme == "http" && !insecure && !policy.allow_http() {
+            bail!("Refusing to fetch package over plain HTTP, pass `--insecure` to override")
+        }
+
+        let package_digest = if require_signer.is_empty() {
+            None
+        } else {
+            if !remote {
+                bail!("`--require-signer` is only supported for packages fetched over HTTP(S)")
+            }
+            let trusted = require_signer
+                .iter()
+                .map(VerifyingKey::load)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let spec: TagSpec = spec.parse()?;
+            let tag_entry = public_client(scheme, &spec.host, &ca_bundle)?
+                .tag(&spec.ctx)
+                .get()
+                .context("Failed to retrieve package information")?;
+            let entry = verify_entry(&tag_entry, &trusted)
+                .context("Refusing to deploy package without a trusted signature")?;
+
+            // The Keep verifies the package it fetches against the signed digest.
+            let digest = serde_json::to_value(entry.meta.hash)
+                .and_then(serde_json::from_value)
+                .context("Failed to encode the signed package digest")?;
+            Some(digest)
+        };
 
-        let backend = backend.pick()?;
-        // TODO: Only allow secure backends
-        // https://github.com/enarx/enarx/issues/1850
-        let exec = EXECS
-            .iter()
-            .find(|w| w.with_backend(backend))
-            .ok_or_else(|| anyhow!("no supported exec found"))
-            .map(|b| b.exec())?;
+        let backend = backend_options.pick()?;
+        let shim = backend_options.shim(backend)?;
+        logging::set_context("backend", backend.name());
+        logging::set_context("keep", logging::keep_id());
+        logging::set_context("package", package.as_str());
+        if remote && policy.is_insecure_backend(backend.name()) {
+            if !insecure {
+                bail!(
+                    "Refusing to deploy remote package to the insecure `{}` backend, pass `--insecure` to override",
+                    backend.name()
+                )
+            }
+            eprintln!(
+                "WARNING: deploying to the `{}` backend, which does not provide hardware isolation",
+                backend.name()
+            );
+        }
+        let exec = load_exec(exec.as_deref(), backend)?;
 
         #[cfg(not(feature = "gdb"))]
         let gdblisten = None;
@@ -60,10 +165,17 @@ impl Options {
         #[cfg(feature = "gdb")]
         let gdblisten = Some(gdblisten);
 
-        let signatures = get_signatures(signatures)?;
+        let signatures = get_signatures(signatures, backend)?;
 
-        let (host, user, repo, tag) = parse_tag(&package)?;
-        let addr = format!("https://{host}/api/v0.1.0/{user}/{repo}/_tag/{tag}/tree");
+        let ca_bundle = ca_bundle
+            .map(|path| {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read CA bundle at `{path}`"))
+            })
+            .transpose()?;
+
+        let (host, user, repo, tag) = parse_tag(spec)?;
+        let addr = format!("{scheme}://{host}/api/v0.1.0/{user}/{repo}/_tag/{tag}/tree");
         let package: Url = addr
             .parse()
             .with_context(|| format!("Failed to parse URL: {addr}"))?;
@@ -105,14 +217,33 @@ impl Options {
                     Ok(pkg)
                 };
 
-                run_package(backend, exec, signatures, gdblisten, get_pkg)?
+                let options = RunOptions {
+                    gdblisten,
+                    ca_bundle: None,
+                    insecure_keys: insecure,
+                    package_digest: None,
+                    shutdown_grace,
+                    metrics_listen,
+                    memory,
+                };
+                run_package(backend, shim, exec, signatures, options, get_pkg)?
             }
 
             // The WASM module and config will be downloaded from a remote by exec-wasmtime
-            // TODO: Disallow `http` or guard by an `--insecure` flag
-            "http" | "https" => run_package(backend, exec, signatures, gdblisten, || {
-                Ok(Package::Remote(package))
-            })?,
+            "http" | "https" => {
+                let options = RunOptions {
+                    gdblisten,
+                    ca_bundle,
+                    insecure_keys: insecure,
+                    package_digest,
+                    shutdown_grace,
+                    metrics_listen,
+                    memory,
+                };
+                run_package(backend, shim, exec, signatures, options, || {
+                    Ok(Package::Remote(package))
+                })?
+            }
 
             s => bail!("unsupported scheme: {}", s),
         };
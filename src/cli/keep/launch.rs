@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Request, Response, SocketOptions};
+
+use anyhow::{bail, Context};
+use clap::Args;
+
+/// Launch a Keep in the background with `enarx daemon`.
+///
+/// The arguments are those of `enarx run` or `enarx deploy`, e.g.
+/// `enarx keep launch -- deploy user/repo:1.0.0`. The ID of the new Keep is
+/// printed on success. Relative paths are resolved against the current
+/// directory, not the one of the daemon.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    socket: SocketOptions,
+
+    /// Command and arguments of the Keep, starting with `run` or `deploy`
+    #[clap(value_name = "ARGS", required = true, last = true)]
+    args: Vec<String>,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let cwd = std::env::current_dir().context("Failed to get the current directory")?;
+        match self.socket.request(&Request::Launch {
+            args: self.args,
+            cwd,
+        })? {
+            Response::Launched { id } => println!("{id}"),
+            response => bail!("Unexpected response from the daemon: {:?}", response),
+        }
+        Ok(())
+    }
+}
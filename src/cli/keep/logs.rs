@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{KeepStatus, Request, Response, SocketOptions};
+
+use anyhow::bail;
+use clap::Args;
+
+/// Print the logs of a Keep of `enarx daemon`.
+///
+/// If the Keep has terminated, this exits with the exit status of the Keep.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    socket: SocketOptions,
+
+    /// ID of the Keep, as printed by `enarx keep ls`
+    #[clap(value_name = "ID")]
+    id: String,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let (keep, logs) = match self.socket.request(&Request::Logs { id: self.id })? {
+            Response::Logs { keep, logs } => (keep, logs),
+            response => bail!("Unexpected response from the daemon: {:?}", response),
+        };
+        print!("{logs}");
+
+        match keep.status {
+            KeepStatus::Running => Ok(()),
+            KeepStatus::Exited { code } => std::process::exit(code),
+            KeepStatus::Killed { signal } => std::process::exit(128 + signal),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Request, Response, SocketOptions};
+
+use anyhow::bail;
+use clap::Args;
+
+/// List the Keeps of `enarx daemon`.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    socket: SocketOptions,
+
+    /// Emit JSON rather than human-readable output
+    #[clap(short, long)]
+    json: bool,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let keeps = match self.socket.request(&Request::List)? {
+            Response::List { keeps } => keeps,
+            response => bail!("Unexpected response from the daemon: {:?}", response),
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&keeps)?);
+        } else {
+            println!("{:<16}  {:>8}  {:<20}  COMMAND", "ID", "PID", "STATUS");
+            for keep in keeps {
+                println!(
+                    "{:<16}  {:>8}  {:<20}  {}",
+                    keep.id,
+                    keep.pid,
+                    keep.status.to_string(),
+                    keep.args.join(" ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod launch;
+mod logs;
+mod ls;
+mod stop;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use camino::Utf8PathBuf;
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+
+/// Commands for managing the Keeps of a running `enarx daemon`.
+#[derive(Subcommand, Debug)]
+pub enum Subcommands {
+    Launch(launch::Options),
+    Ls(ls::Options),
+    Stop(stop::Options),
+    Logs(logs::Options),
+}
+
+impl Subcommands {
+    pub fn dispatch(self) -> anyhow::Result<()> {
+        match self {
+            Self::Launch(cmd) => cmd.execute(),
+            Self::Ls(cmd) => cmd.execute(),
+            Self::Stop(cmd) => cmd.execute(),
+            Self::Logs(cmd) => cmd.execute(),
+        }
+    }
+}
+
+/// Options to locate the socket of `enarx daemon`
+#[derive(Args, Debug)]
+pub struct SocketOptions {
+    /// Path of the Unix socket of the daemon, by default
+    /// `$XDG_RUNTIME_DIR/enarx/daemon.sock` or `/run/enarx/daemon.sock`
+    #[clap(long, env = "ENARX_DAEMON_SOCKET", value_name = "SOCKET")]
+    socket: Option<Utf8PathBuf>,
+}
+
+impl SocketOptions {
+    pub fn path(&self) -> PathBuf {
+        match &self.socket {
+            Some(path) => path.into(),
+            None => dirs::runtime_dir()
+                .unwrap_or_else(|| "/run".into())
+                .join("enarx")
+                .join("daemon.sock"),
+        }
+    }
+
+    /// Send a request to the daemon and wait for its response.
+    pub fn request(&self, request: &Request) -> anyhow::Result<Response> {
+        let path = self.path();
+        let mut stream = UnixStream::connect(&path).with_context(|| {
+            format!(
+                "Failed to connect to the enarx daemon at `{}`, is `enarx daemon` running?",
+                path.display()
+            )
+        })?;
+        serde_json::to_writer(&mut stream, request).context("Failed to send request")?;
+        stream
+            .write_all(b"\n")
+            .and_then(|()| stream.shutdown(Shutdown::Write))
+            .context("Failed to send request")?;
+
+        let mut line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut line)
+            .context("Failed to receive response")?;
+        match serde_json::from_str(&line).context("Failed to parse response")? {
+            Response::Error { message } => bail!("{}", message),
+            response => Ok(response),
+        }
+    }
+}
+
+/// A request to the daemon, sent as a line of JSON.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "request", rename_all = "kebab-case")]
+pub enum Request {
+    /// Launch a Keep with the arguments of `enarx run` or `enarx deploy`,
+    /// relative paths of which are resolved against `cwd`
+    Launch { args: Vec<String>, cwd: PathBuf },
+
+    /// List all Keeps
+    List,
+
+    /// Stop a running Keep
+    Stop { id: String },
+
+    /// Fetch the logs and status of a Keep
+    Logs { id: String },
+}
+
+/// A response of the daemon, sent as a line of JSON.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "response", rename_all = "kebab-case")]
+pub enum Response {
+    Launched { id: String },
+    List { keeps: Vec<KeepInfo> },
+    Stopped { id: String },
+    Logs { keep: KeepInfo, logs: String },
+    Error { message: String },
+}
+
+/// A Keep run by the daemon.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeepInfo {
+    pub id: String,
+    pub pid: u32,
+    pub args: Vec<String>,
+    pub status: KeepStatus,
+}
+
+/// The status of a Keep run by the daemon.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum KeepStatus {
+    Running,
+    Exited { code: i32 },
+    Killed { signal: i32 },
+}
+
+impl std::fmt::Display for KeepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::Exited { code } => write!(f, "exited ({code})"),
+            Self::Killed { signal } => write!(f, "killed (signal {signal})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol() {
+        let request = Request::Launch {
+            args: vec!["deploy".into(), "user/repo:1.0.0".into()],
+            cwd: "/home/user".into(),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"request":"launch","args":["deploy","user/repo:1.0.0"],"cwd":"/home/user"}"#
+        );
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request);
+
+        let response = Response::List {
+            keeps: vec![KeepInfo {
+                id: "0011223344556677".into(),
+                pid: 42,
+                args: vec!["run".into(), "app.wasm".into()],
+                status: KeepStatus::Exited { code: 1 },
+            }],
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), response);
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{Request, Response, SocketOptions};
+
+use anyhow::bail;
+use clap::Args;
+
+/// Stop a Keep of `enarx daemon`.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    socket: SocketOptions,
+
+    /// ID of the Keep, as printed by `enarx keep ls`
+    #[clap(value_name = "ID")]
+    id: String,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        match self.socket.request(&Request::Stop { id: self.id })? {
+            Response::Stopped { .. } => Ok(()),
+            response => bail!("Unexpected response from the daemon: {:?}", response),
+        }
+    }
+}
@@ -1,32 +1,97 @@
 // SPDX-License-Identifier: Apache-2.0
 
-//#[cfg(enarx_with_shim)]
-//mod sgx;
-#[cfg(enarx_with_shim)]
 mod sev;
+mod sgx;
+mod sign;
 
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+
+use anyhow::Context;
+use camino::Utf8Path;
 use clap::Subcommand;
+use p384::ecdsa::{SigningKey, VerifyingKey};
+use p384::pkcs8::{DecodePrivateKey as _, DecodePublicKey as _};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey, EncodeRsaPrivateKey};
+use rsa::pkcs8::{DecodePrivateKey as _, DecodePublicKey as _, LineEnding};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sgx::crypto::{rcrypto::*, *};
 
-/// Commands for utilizing keys to interact with Enarx.
+/// Commands for managing the keys used to sign Enarx Keeps.
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
-    
-    //#[cfg(enarx_with_shim)]
-    //#[clap(subcommand)]
-    //Sgx(sgx::Subcommands),
-    
-    #[cfg(enarx_with_shim)]
+    #[clap(subcommand)]
+    Sgx(sgx::Subcommands),
     #[clap(subcommand)]
     Sev(sev::Subcommands),
+    Sign(sign::Options),
 }
 
 impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<()> {
         match self {
-            //#[cfg(enarx_with_shim)]
-            //Self::Sgx(subcmd) => subcmd.dispatch(),
-            #[cfg(enarx_with_shim)]
+            Self::Sgx(subcmd) => subcmd.dispatch(),
             Self::Sev(subcmd) => subcmd.dispatch(),
+            Self::Sign(cmd) => cmd.execute(),
         }
     }
 }
+
+/// Load an SGX RSA private key in PKCS#8 or PKCS#1 PEM form.
+fn load_rsa_key(path: &Utf8Path) -> anyhow::Result<RsaPrivateKey> {
+    let pem = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SGX private key file `{path}`"))?;
+    RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .with_context(|| format!("Failed to parse SGX private key `{path}`"))
+}
+
+/// Load an SGX RSA public key from a private or public key in PEM form.
+fn load_rsa_public_key(path: &Utf8Path) -> anyhow::Result<RsaPublicKey> {
+    let pem = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SGX key file `{path}`"))?;
+    RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map(|key| key.to_public_key())
+        .or_else(|_| RsaPublicKey::from_public_key_pem(&pem))
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(&pem))
+        .with_context(|| format!("Failed to parse SGX key `{path}`"))
+}
+
+/// Load an SGX RSA private key in PKCS#8 or PKCS#1 PEM form for signing.
+fn load_sgx_key(path: &Utf8Path) -> anyhow::Result<RS256PrivateKey> {
+    let pem = load_rsa_key(path)?
+        .to_pkcs1_pem(LineEnding::LF)
+        .context("Failed to encode SGX private key")?;
+    RS256PrivateKey::from_pem(&pem).with_context(|| format!("Invalid SGX private key `{path}`"))
+}
+
+/// Load a SEV P-384 private key in PKCS#8 PEM form.
+fn load_sev_key(path: &Utf8Path) -> anyhow::Result<SigningKey> {
+    let pem = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SEV private key file `{path}`"))?;
+    SigningKey::from_pkcs8_pem(&pem)
+        .with_context(|| format!("Failed to parse SEV private key `{path}`"))
+}
+
+/// Load a SEV P-384 public key from a private or public key in PEM form.
+fn load_sev_public_key(path: &Utf8Path) -> anyhow::Result<VerifyingKey> {
+    let pem = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SEV key file `{path}`"))?;
+    SigningKey::from_pkcs8_pem(&pem)
+        .map(|key| key.verifying_key())
+        .or_else(|_| p384::PublicKey::from_public_key_pem(&pem).map(|key| VerifyingKey::from(&key)))
+        .with_context(|| format!("Failed to parse SEV key `{path}`"))
+}
+
+/// Write a private key to a new file at `path`, only readable by the owner.
+fn write_key(path: &Utf8Path, pem: &str) -> anyhow::Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut file| file.write_all(pem.as_bytes()))
+        .with_context(|| format!("Failed to write private key to `{path}`"))
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::write_key;
+
+use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use p384::pkcs8::{EncodePrivateKey, LineEnding};
+use p384::SecretKey;
+use rand::rngs::OsRng;
+
+/// Generate SEV P-384 ID and author signing keys.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Path to write the PKCS#8 PEM-encoded ID key to
+    #[clap(long)]
+    id_key: Utf8PathBuf,
+
+    /// Path to write the PKCS#8 PEM-encoded author key to
+    #[clap(long)]
+    author_key: Option<Utf8PathBuf>,
+}
+
+fn create(path: &Utf8Path) -> anyhow::Result<()> {
+    let pem = SecretKey::random(&mut OsRng)
+        .to_pkcs8_pem(LineEnding::LF)
+        .context("Failed to encode SEV private key")?;
+    write_key(path, &pem)
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        create(&self.id_key)?;
+        if let Some(ref author_key) = self.author_key {
+            create(author_key)?;
+        }
+        Ok(())
+    }
+}
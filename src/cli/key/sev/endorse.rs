@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::{load_sev_key, load_sev_public_key};
+use super::{load_signature, public_key, Endorsement};
+use crate::backend::sev::snp::ByteSized;
+
+use std::fs;
+
+use anyhow::{bail, Context};
+use camino::Utf8PathBuf;
+use clap::Args;
+
+/// Endorse a SEV ID key with an author key.
+///
+/// The endorsement can be passed to `enarx key sign --sev-endorsement`, so that
+/// only the ID key is needed to sign Keeps. If the author key is only available
+/// to an external signer, write the data to sign with `--export` and pass the
+/// resulting DER-encoded ECDSA P-384 SHA-384 signature with `--signature`.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// SEV P-384 ID key in PEM form, only the public key is needed
+    #[clap(long)]
+    id_key: Utf8PathBuf,
+
+    /// SEV P-384 author private key in PEM form, or the public key with `--signature`
+    #[clap(long, required_unless_present = "export")]
+    author_key: Option<Utf8PathBuf>,
+
+    /// Externally created signature of the data written by `--export`
+    #[clap(long, requires = "author_key")]
+    signature: Option<Utf8PathBuf>,
+
+    /// Write the data to be signed by an external signer to this file
+    #[clap(long, value_name = "FILE", conflicts_with_all = &["author_key", "signature", "out"])]
+    export: Option<Utf8PathBuf>,
+
+    /// Path to write the endorsement to
+    #[clap(long, required_unless_present = "export")]
+    out: Option<Utf8PathBuf>,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let id_key = load_sev_public_key(&self.id_key)?;
+
+        if let Some(ref path) = self.export {
+            return fs::write(path, public_key(&id_key)?.as_bytes())
+                .with_context(|| format!("Failed to write `{path}`"));
+        }
+
+        let endorsement = match (self.author_key, self.signature) {
+            (Some(author_key), Some(sig)) => Endorsement::with_signature(
+                &id_key,
+                &load_sev_public_key(&author_key)?,
+                &load_signature(&sig)?,
+            )?,
+            (Some(author_key), None) => Endorsement::sign(&id_key, &load_sev_key(&author_key)?)?,
+            (None, _) => bail!("An author key is required"),
+        };
+
+        let out = self.out.context("An output path is required")?;
+        fs::write(&out, endorsement.as_bytes())
+            .with_context(|| format!("Failed to write endorsement to `{out}`"))
+    }
+}
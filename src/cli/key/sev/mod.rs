@@ -1,19 +1,122 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod create;
 mod digest;
+mod endorse;
 
+use crate::backend::sev::snp::sign::{PublicKey, Signature};
+use crate::backend::sev::snp::ByteSized;
+
+use std::fs;
+
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
 use clap::Subcommand;
+use p384::ecdsa::signature::{Signer as _, Verifier as _};
+use p384::ecdsa::{self, SigningKey, VerifyingKey};
+use p384::elliptic_curve::sec1::Coordinates;
+use p384::EncodedPoint;
 
 /// SEV-specific functionality
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
+    Create(create::Options),
     Digest(digest::Options),
+    Endorse(endorse::Options),
 }
 
 impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<()> {
         match self {
+            Self::Create(cmd) => cmd.execute(),
             Self::Digest(cmd) => cmd.execute(),
+            Self::Endorse(cmd) => cmd.execute(),
         }
     }
 }
+
+/// Convert a SEV P-384 key to the public key structure used in the `ID_AUTH` block.
+pub fn public_key(key: &VerifyingKey) -> Result<PublicKey> {
+    let verifying_key: EncodedPoint = key.to_encoded_point(false);
+    let (mut r, mut s) = match verifying_key.coordinates() {
+        Coordinates::Uncompressed { x, y } => (x.to_vec(), y.to_vec()),
+        _ => bail!("Invalid verifying key"),
+    };
+    r.reverse();
+    s.reverse();
+
+    let mut public_key = PublicKey::default();
+    public_key.component.r[..r.len()].copy_from_slice(&r);
+    public_key.component.s[..s.len()].copy_from_slice(&s);
+    Ok(public_key)
+}
+
+/// Convert a P-384 ECDSA signature to the signature structure used in the `ID_AUTH` block.
+pub fn signature(sig: &ecdsa::Signature) -> Signature {
+    let r = sig.r().as_ref().to_le_bytes();
+    let s = sig.s().as_ref().to_le_bytes();
+
+    let mut signature = Signature::default();
+    signature.component.r[..r.as_slice().len()].copy_from_slice(r.as_slice());
+    signature.component.s[..s.as_slice().len()].copy_from_slice(s.as_slice());
+    signature
+}
+
+/// Load a DER-encoded P-384 ECDSA signature created by an external signer.
+pub fn load_signature(path: &Utf8Path) -> Result<ecdsa::Signature> {
+    let der = fs::read(path).with_context(|| format!("Failed to read signature `{path}`"))?;
+    ecdsa::Signature::from_der(&der)
+        .with_context(|| format!("Failed to parse DER-encoded signature `{path}`"))
+}
+
+/// Endorsement of a SEV ID key by an author key.
+///
+/// This is the part of the `ID_AUTH` block which does not depend on the signed
+/// `ID_BLOCK`, so that it can be created once, offline, and Keeps can be signed
+/// without access to the author private key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct Endorsement {
+    pub id_key: PublicKey,
+    pub id_key_sig: Signature,
+    pub author_key: PublicKey,
+}
+
+impl ByteSized for Endorsement {}
+
+impl Endorsement {
+    /// Endorse `id_key` with the author private key.
+    pub fn sign(id_key: &VerifyingKey, author_key: &SigningKey) -> Result<Self> {
+        let id_key = public_key(id_key)?;
+        let sig = author_key.sign(id_key.as_bytes());
+        Ok(Self {
+            id_key,
+            id_key_sig: signature(&sig),
+            author_key: public_key(&author_key.verifying_key())?,
+        })
+    }
+
+    /// Endorse `id_key` with a signature created by an external signer.
+    pub fn with_signature(
+        id_key: &VerifyingKey,
+        author_key: &VerifyingKey,
+        sig: &ecdsa::Signature,
+    ) -> Result<Self> {
+        let id_key = public_key(id_key)?;
+        author_key
+            .verify(id_key.as_bytes(), sig)
+            .context("Signature of the ID key does not match the author key")?;
+        Ok(Self {
+            id_key,
+            id_key_sig: signature(sig),
+            author_key: public_key(author_key)?,
+        })
+    }
+
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let bytes =
+            fs::read(path).with_context(|| format!("Failed to read endorsement `{path}`"))?;
+        // Safety: `Endorsement` is plain old data and the length is checked.
+        unsafe { Self::from_bytes(&bytes) }.with_context(|| format!("Invalid endorsement `{path}`"))
+    }
+}
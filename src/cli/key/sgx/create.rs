@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::write_key;
+
+use anyhow::Context;
+use camino::Utf8PathBuf;
+use clap::Args;
+use rand::rngs::OsRng;
+use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+use rsa::{BigUint, RsaPrivateKey};
+
+/// Size of the SGX signing key modulus in bits
+const MODULUS_BITS: usize = 3072;
+
+/// Public exponent required for SGX signing keys
+const EXPONENT: u8 = 3;
+
+/// Generate an SGX RSA-3072 signing key with public exponent 3.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Path to write the PKCS#8 PEM-encoded private key to
+    #[clap(long)]
+    out: Utf8PathBuf,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let key = RsaPrivateKey::new_with_exp(&mut OsRng, MODULUS_BITS, &BigUint::from(EXPONENT))
+            .context("Failed to generate SGX private key")?;
+        let pem = key
+            .to_pkcs8_pem(LineEnding::LF)
+            .context("Failed to encode SGX private key")?;
+        write_key(&self.out, &pem)
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::super::load_rsa_key;
+use crate::cli::hex;
+
+use anyhow::bail;
+use camino::Utf8PathBuf;
+use clap::Args;
+use ring::digest;
+use rsa::PublicKeyParts;
+
+/// Size of the SGX signing key modulus in bytes
+const MODULUS_SIZE: usize = 384;
+
+/// Print the MRSIGNER value of Keeps signed with an SGX key.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// SGX RSA private key in PEM form
+    #[clap(long)]
+    key: Utf8PathBuf,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let key = load_rsa_key(&self.key)?;
+
+        // MRSIGNER is the SHA-256 digest of the little-endian modulus.
+        let mut modulus = key.n().to_bytes_le();
+        if modulus.len() > MODULUS_SIZE {
+            bail!("SGX private key `{}` is not an RSA-3072 key", self.key);
+        }
+        modulus.resize(MODULUS_SIZE, 0);
+
+        println!(
+            "{}",
+            hex(digest::digest(&digest::SHA256, &modulus).as_ref())
+        );
+        Ok(())
+    }
+}
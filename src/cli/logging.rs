@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Log targets and formats beyond the ones provided by `env_logger`.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context};
+use env_logger::{Builder, Target};
+#[cfg(unix)]
+use log::Level;
+use log::Record;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+
+#[cfg(unix)]
+use std::os::unix::{
+    io::{AsRawFd, FromRawFd},
+    net::UnixDatagram,
+};
+
+/// Path of the syslog socket.
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Path of the socket of the journald native protocol.
+#[cfg(unix)]
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Number of rotated log files kept next to the current one.
+const ROTATED_FILES: usize = 3;
+
+/// Default size in bytes at which log files are rotated.
+pub const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Identifier of `enarx` in syslog and the journal.
+const IDENTIFIER: &str = "enarx";
+
+/// Fields added to every log record, e.g. the backend and package of the Keep.
+static CONTEXT: Lazy<RwLock<BTreeMap<&'static str, String>>> = Lazy::new(Default::default);
+
+/// Add the field `key` to all subsequent log records.
+pub fn set_context(key: &'static str, value: impl Into<String>) {
+    CONTEXT.write().unwrap().insert(key, value.into());
+}
+
+/// Returns the identifier of this Keep, used to correlate its log records.
+///
+/// Keeps launched by `enarx daemon` are passed theirs in `ENARX_KEEP_ID`.
+pub fn keep_id() -> String {
+    std::env::var(KEEP_ID_ENV).unwrap_or_else(|_| new_keep_id())
+}
+
+/// Environment variable holding the identifier of a Keep launched by `enarx daemon`.
+pub const KEEP_ID_ENV: &str = "ENARX_KEEP_ID";
+
+/// Returns a new random identifier for a Keep.
+pub fn new_keep_id() -> String {
+    use ring::rand::{SecureRandom, SystemRandom};
+
+    let mut id = [0u8; 8];
+    // Fall back to the process ID rather than failing to run the Keep.
+    if SystemRandom::new().fill(&mut id).is_err() {
+        id = u64::from(std::process::id()).to_be_bytes();
+    }
+    id.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Represents targets for logging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LogTarget {
+    Stdout,
+    Stderr,
+    /// A file, which is rotated once it exceeds the maximum size
+    File(PathBuf),
+    #[cfg(unix)]
+    Syslog,
+    /// The systemd journal, using its native protocol
+    #[cfg(unix)]
+    Journald,
+}
+
+/// Convert a str to a LogTarget. This is how Clap parses CLI args.
+impl FromStr for LogTarget {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("file:") {
+            if path.is_empty() {
+                return Err(anyhow!("missing path in log target {:?}", s));
+            }
+            return Ok(Self::File(path.into()));
+        }
+        match s.to_ascii_lowercase().as_str() {
+            "stdout" => Ok(Self::Stdout),
+            "stderr" => Ok(Self::Stderr),
+            #[cfg(unix)]
+            "syslog" => Ok(Self::Syslog),
+            #[cfg(unix)]
+            "journald" => Ok(Self::Journald),
+            _ => Err(anyhow!("unknown log target {:?}", s)),
+        }
+    }
+}
+
+/// Represents formats of log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per record, with the context fields
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("unknown log format {:?}", s)),
+        }
+    }
+}
+
+/// Set the target and format of `builder`.
+pub fn configure(
+    builder: &mut Builder,
+    target: &LogTarget,
+    format: LogFormat,
+    max_size: u64,
+) -> anyhow::Result<()> {
+    match target {
+        LogTarget::Stdout => {
+            builder.target(Target::Stdout);
+        }
+        LogTarget::Stderr => {
+            builder.target(Target::Stderr);
+        }
+        LogTarget::File(path) => {
+            let file = RotatingFile::open(path, max_size)
+                .with_context(|| format!("Failed to open log file `{}`", path.display()))?;
+            builder.target(Target::Pipe(Box::new(file)));
+        }
+        #[cfg(unix)]
+        LogTarget::Syslog => {
+            let socket = Datagram::connect(SYSLOG_SOCKET)
+                .with_context(|| format!("Failed to connect to syslog at `{SYSLOG_SOCKET}`"))?;
+            builder.target(Target::Pipe(Box::new(socket)));
+            builder.format(move |buf, record| {
+                write!(
+                    buf,
+                    "<{}>{IDENTIFIER}[{}]: ",
+                    syslog_priority(record.level()),
+                    std::process::id()
+                )?;
+                match format {
+                    LogFormat::Text => write!(buf, "[{}] {}", record.target(), record.args()),
+                    LogFormat::Json => {
+                        serde_json::to_writer(&mut *buf, &json(record)).map_err(io::Error::from)
+                    }
+                }
+            });
+            return Ok(());
+        }
+        #[cfg(unix)]
+        LogTarget::Journald => {
+            let socket = Datagram::connect(JOURNALD_SOCKET)
+                .with_context(|| format!("Failed to connect to journald at `{JOURNALD_SOCKET}`"))?;
+            builder.target(Target::Pipe(Box::new(socket)));
+            builder.format(|buf, record| buf.write_all(&journald(record)));
+            return Ok(());
+        }
+    }
+
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            serde_json::to_writer(&mut *buf, &json(record))?;
+            writeln!(buf)
+        });
+    }
+    Ok(())
+}
+
+/// Returns `record` along with the context fields as a JSON object.
+fn json(record: &Record<'_>) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+
+    let mut fields = Map::new();
+    fields.insert("timestamp".into(), timestamp.into());
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+    for (key, value) in CONTEXT.read().unwrap().iter() {
+        fields.insert(key.to_string(), value.as_str().into());
+    }
+    Value::Object(fields)
+}
+
+/// Severity of `level` as defined by syslog, in the user facility.
+#[cfg(unix)]
+fn syslog_priority(level: Level) -> u8 {
+    const USER: u8 = 1 << 3;
+    USER | journald_priority(level)
+}
+
+/// Severity of `level` as defined by syslog.
+#[cfg(unix)]
+fn journald_priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Encode `record` along with the context fields for the journald native protocol.
+#[cfg(unix)]
+fn journald(record: &Record<'_>) -> Vec<u8> {
+    fn field(buf: &mut Vec<u8>, name: &str, value: &str) {
+        buf.extend_from_slice(name.as_bytes());
+        // Values containing newlines must be length-prefixed.
+        if value.contains('\n') {
+            buf.push(b'\n');
+            buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            buf.push(b'=');
+        }
+        buf.extend_from_slice(value.as_bytes());
+        buf.push(b'\n');
+    }
+
+    let mut buf = Vec::new();
+    field(&mut buf, "MESSAGE", &record.args().to_string());
+    field(
+        &mut buf,
+        "PRIORITY",
+        &journald_priority(record.level()).to_string(),
+    );
+    field(&mut buf, "SYSLOG_IDENTIFIER", IDENTIFIER);
+    field(&mut buf, "TARGET", record.target());
+    if let Some(file) = record.file() {
+        field(&mut buf, "CODE_FILE", file);
+    }
+    if let Some(line) = record.line() {
+        field(&mut buf, "CODE_LINE", &line.to_string());
+    }
+    for (key, value) in CONTEXT.read().unwrap().iter() {
+        let name = format!("ENARX_{}", key.to_ascii_uppercase());
+        field(&mut buf, &name, value);
+    }
+    buf
+}
+
+/// Move `fd` out of the range of the lowest free file descriptors.
+///
+/// `run_package` requires file descriptor 3 to be free, so long-lived log
+/// targets must not occupy it.
+#[cfg(unix)]
+fn high_fd<T: AsRawFd + FromRawFd>(fd: T) -> io::Result<T> {
+    const MIN_FD: libc::c_int = 64;
+
+    let high = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, MIN_FD) };
+    if high < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Safety: `high` is a new file descriptor owned by nothing else.
+    Ok(unsafe { T::from_raw_fd(high) })
+}
+
+#[cfg(not(unix))]
+fn high_fd<T>(fd: T) -> io::Result<T> {
+    Ok(fd)
+}
+
+/// A connected Unix datagram socket, to which every record is sent as one datagram.
+#[cfg(unix)]
+struct Datagram(UnixDatagram);
+
+#[cfg(unix)]
+impl Datagram {
+    fn connect(path: &str) -> io::Result<Self> {
+        let socket = high_fd(UnixDatagram::unbound()?)?;
+        socket.connect(path)?;
+        Ok(Self(socket))
+    }
+}
+
+#[cfg(unix)]
+impl Write for Datagram {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A log file, which is moved to `<path>.1` once it exceeds the maximum size.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_size: u64) -> io::Result<Self> {
+        let file = Self::append(path)?;
+        Ok(Self {
+            path: path.into(),
+            size: file.metadata()?.len(),
+            file,
+            max_size,
+        })
+    }
+
+    fn append(path: &Path) -> io::Result<File> {
+        high_fd(OpenOptions::new().create(true).append(true).open(path)?)
+    }
+
+    /// Path of the `n`th rotated file.
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..ROTATED_FILES).rev() {
+            match fs::rename(self.rotated(n), self.rotated(n + 1)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        self.file = Self::append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        assert_eq!("stderr".parse::<LogTarget>().unwrap(), LogTarget::Stderr);
+        assert_eq!(
+            "file:/var/log/enarx.log".parse::<LogTarget>().unwrap(),
+            LogTarget::File("/var/log/enarx.log".into())
+        );
+        assert!("file:".parse::<LogTarget>().is_err());
+        #[cfg(unix)]
+        assert_eq!(
+            "journald".parse::<LogTarget>().unwrap(),
+            LogTarget::Journald
+        );
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn journald_fields() {
+        set_context("backend", "sgx");
+        let buf = journald(
+            &Record::builder()
+                .args(format_args!("two\nlines"))
+                .level(Level::Warn)
+                .target("enarx")
+                .build(),
+        );
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&9u64.to_le_bytes());
+        expected.extend_from_slice(b"two\nlines\n");
+        expected.extend_from_slice(b"PRIORITY=4\nSYSLOG_IDENTIFIER=enarx\nTARGET=enarx\n");
+        assert!(buf.starts_with(&expected));
+        assert!(buf.ends_with(b"ENARX_BACKEND=sgx\n"));
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("enarx.log");
+        let mut file = RotatingFile::open(&path, 8).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n", "fifth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fifth\n");
+        assert_eq!(fs::read_to_string(file.rotated(1)).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(file.rotated(3)).unwrap(), "second\n");
+        assert!(!file.rotated(4).exists());
+    }
+}
@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::backend::sev::snp::launch::IdBlock;
+use crate::backend::sev::snp::ByteSized;
+use crate::backend::{Backend, BACKENDS};
+use crate::cli::{hex, IdentityOptions};
+use crate::exec::EXECS;
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter};
+use std::mem::size_of;
+use std::ops::Deref;
+
+use anyhow::{bail, Context, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+use serde::Serialize;
+use sgx::signature::Body;
+
+/// Offset of the enclave measurement in the SGX SIGSTRUCT body
+const SGX_MRENCLAVE: usize = 60;
+
+/// Offset of the ISV product ID in the SGX SIGSTRUCT body
+const SGX_ISV_PROD_ID: usize = 124;
+
+/// Offset of the ISV SVN in the SGX SIGSTRUCT body
+const SGX_ISV_SVN: usize = 126;
+
+/// Calculate the expected measurements of a Keep for every backend.
+///
+/// No hardware support is required, so that the reference values for a
+/// verification policy can be calculated on any machine.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Exec binary to measure instead of the built-in one
+    #[clap(value_name = "BINARY")]
+    pub binpath: Option<Utf8PathBuf>,
+
+    /// Only calculate the measurement for this backend
+    #[clap(long)]
+    backend: Option<String>,
+
+    /// Emit JSON rather than human-readable output
+    #[clap(short, long)]
+    json: bool,
+
+    #[clap(flatten)]
+    identity: IdentityOptions,
+}
+
+/// Expected measurement of a Keep
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+enum Measurement {
+    #[serde(rename_all = "kebab-case")]
+    Sgx {
+        mrenclave: String,
+        isv_prod_id: u16,
+        isv_svn: u16,
+    },
+    #[serde(rename_all = "kebab-case")]
+    Sev {
+        measurement: String,
+        policy: String,
+        family_id: String,
+        image_id: String,
+        guest_svn: u32,
+    },
+}
+
+impl Measurement {
+    fn sgx(blob: &[u8]) -> Result<Self> {
+        if blob.len() != size_of::<Body>() {
+            bail!("Invalid length of SGX measurement data");
+        }
+        let u16_at = |offset: usize| u16::from_le_bytes([blob[offset], blob[offset + 1]]);
+        Ok(Self::Sgx {
+            mrenclave: hex(&blob[SGX_MRENCLAVE..SGX_MRENCLAVE + 32]),
+            isv_prod_id: u16_at(SGX_ISV_PROD_ID),
+            isv_svn: u16_at(SGX_ISV_SVN),
+        })
+    }
+
+    fn sev(blob: &[u8]) -> Result<Self> {
+        // Safety: `IdBlock` is plain old data and the length is checked.
+        let id_block = unsafe { IdBlock::from_bytes(blob) }
+            .context("Invalid length of SEV measurement data")?;
+        Ok(Self::Sev {
+            measurement: hex(&id_block.launch_digest),
+            policy: format!("{:#x}", id_block.policy),
+            family_id: hex(&id_block.family_id),
+            image_id: hex(&id_block.image_id),
+            guest_svn: id_block.guest_svn,
+        })
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sgx {
+                mrenclave,
+                isv_prod_id,
+                isv_svn,
+            } => {
+                writeln!(f, "  MRENCLAVE: {mrenclave}")?;
+                writeln!(f, "  ISV product ID: {isv_prod_id}")?;
+                writeln!(f, "  ISV SVN: {isv_svn}")
+            }
+            Self::Sev {
+                measurement,
+                policy,
+                family_id,
+                image_id,
+                guest_svn,
+            } => {
+                writeln!(f, "  Launch measurement: {measurement}")?;
+                writeln!(f, "  Policy: {policy}")?;
+                writeln!(f, "  Family ID: {family_id}")?;
+                writeln!(f, "  Image ID: {image_id}")?;
+                writeln!(f, "  Guest SVN: {guest_svn}")
+            }
+        }
+    }
+}
+
+impl Options {
+    pub fn execute(self) -> Result<()> {
+        use mmarinus::{perms, Map, Private};
+        let binary = if let Some(ref path) = self.binpath {
+            Some(
+                Map::load(&path, Private, perms::Read)
+                    .with_context(|| format!("Failed to load `{path}`"))?,
+            )
+        } else {
+            None
+        };
+
+        let identity = self.identity.identity();
+        let mut measurements = BTreeMap::new();
+
+        for backend in BACKENDS.deref().iter() {
+            let backend: &dyn Backend = backend.deref();
+
+            if matches!(self.backend, Some(ref name) if name != backend.name()) {
+                continue;
+            }
+
+            let parse = match backend.name() {
+                "sgx" => Measurement::sgx,
+                "sev" => Measurement::sev,
+                _ => continue,
+            };
+
+            if backend.shim().is_empty() {
+                continue;
+            }
+
+            let exec = if let Some(ref e) = binary {
+                e.as_ref()
+            } else if let Some(e) = EXECS.iter().find(|w| w.with_backend(backend)) {
+                e.exec()
+            } else {
+                continue;
+            };
+
+            if exec.is_empty() {
+                continue;
+            }
+
+            let blob = backend
+                .hash(backend.shim().as_ref(), exec.as_ref(), &identity)
+                .with_context(|| format!("Failed to measure {} Keep", backend.name()))?;
+            measurements.insert(backend.name(), parse(&blob)?);
+        }
+
+        if let Some(ref name) = self.backend {
+            if measurements.is_empty() {
+                bail!("Measurements are not supported for the {name:?} backend");
+            }
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&measurements)?);
+        } else {
+            for (name, measurement) in measurements {
+                println!("Backend: {name}");
+                print!("{measurement}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Measurement;
+
+    const SEV_IN: [u8; 96] = [
+        255, 165, 145, 93, 184, 17, 227, 134, 166, 124, 80, 99, 74, 210, 44, 73, 78, 253, 225, 255,
+        236, 152, 189, 138, 194, 109, 162, 157, 70, 219, 81, 136, 79, 24, 70, 89, 190, 39, 116,
+        121, 93, 236, 54, 214, 57, 223, 252, 236, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 3, 0, 0, 0,
+        0, 0,
+    ];
+
+    const SGX_IN: [u8; 128] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0,
+        0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+        124, 255, 195, 180, 246, 57, 219, 115, 59, 98, 240, 212, 175, 143, 166, 98, 40, 238, 160,
+        47, 140, 230, 9, 180, 243, 246, 196, 110, 169, 159, 112, 127, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 234, 1, 0,
+    ];
+
+    #[test]
+    fn sgx() {
+        assert_eq!(
+            Measurement::sgx(&SGX_IN).unwrap(),
+            Measurement::Sgx {
+                mrenclave: "7cffc3b4f639db733b62f0d4af8fa66228eea02f8ce609b4f3f6c46ea99f707f"
+                    .into(),
+                isv_prod_id: 0xea00,
+                isv_svn: 1,
+            }
+        );
+        assert!(Measurement::sgx(&SGX_IN[1..]).is_err());
+    }
+
+    #[test]
+    fn sev() {
+        assert_eq!(
+            Measurement::sev(&SEV_IN).unwrap(),
+            Measurement::Sev {
+                measurement: "ffa5915db811e386a67c50634ad22c494efde1ffec98bd8ac26da29d46db51884f184659be2774795dec36d639dffcec".into(),
+                policy: "0x30000".into(),
+                family_id: "01000000000000000000000000000000".into(),
+                image_id: "01000000000000000000000000000000".into(),
+                guest_svn: 1,
+            }
+        );
+        assert!(Measurement::sev(&SEV_IN[1..]).is_err());
+    }
+}
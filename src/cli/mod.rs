@@ -1,27 +1,38 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod attest;
 mod config;
+#[cfg(unix)]
+mod daemon;
 mod deploy;
+#[cfg(unix)]
+mod keep;
 mod key;
+mod logging;
+#[cfg(enarx_with_shim)]
+mod measure;
 mod package;
 mod platform;
 mod repo;
 mod run;
-#[cfg(enarx_with_shim)]
-mod sign;
 mod tree;
 mod unstable;
 mod user;
 
+#[cfg(enarx_with_shim)]
+use crate::backend::Identity;
 use crate::backend::{Backend, Signatures, BACKENDS};
+use crate::exec::EXECS;
+use crate::host_config::HostConfig;
+use logging::{LogFormat, LogTarget};
 
-use std::fs::File;
+use std::borrow::Cow;
+use std::fs::{self, File};
 use std::io::Read;
 use std::ops::Deref;
-use std::str::FromStr;
 
 use anyhow::{anyhow, bail, Context};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Args, Parser, Subcommand};
 use log::info;
 
@@ -47,7 +58,7 @@ pub struct Options {
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<()> {
-        self.logger.init();
+        self.logger.init()?;
 
         info!("logging initialized!");
         info!("CLI opts: {:?}", self);
@@ -61,21 +72,27 @@ impl Options {
 enum Subcommands {
     Run(run::Options),
     Deploy(deploy::Options),
+    #[cfg(unix)]
+    Daemon(daemon::Options),
+    #[cfg(unix)]
+    #[clap(subcommand)]
+    Keep(keep::Subcommands),
     #[clap(subcommand)]
     Config(config::Subcommands),
+    #[clap(subcommand)]
+    Attest(attest::Subcommands),
     #[cfg(enarx_with_shim)]
     #[clap(subcommand)]
     Key(key::Subcommands),
     #[cfg(enarx_with_shim)]
+    Measure(measure::Options),
+    #[cfg(enarx_with_shim)]
     #[clap(subcommand)]
     Platform(platform::Subcommands),
     #[clap(subcommand)]
     Package(package::Subcommands),
     #[clap(subcommand)]
     Repo(repo::Subcommands),
-    #[cfg(enarx_with_shim)]
-    #[clap(hide = true)]
-    Sign(sign::Options),
     #[clap(subcommand, hide = true)]
     Tree(tree::Subcommands),
     #[clap(subcommand)]
@@ -89,14 +106,19 @@ impl Subcommands {
         match self {
             Self::Run(cmd) => cmd.execute(),
             Self::Config(subcmd) => subcmd.dispatch(),
+            Self::Attest(subcmd) => subcmd.dispatch(),
             Self::Deploy(cmd) => cmd.execute(),
+            #[cfg(unix)]
+            Self::Daemon(cmd) => cmd.execute(),
+            #[cfg(unix)]
+            Self::Keep(subcmd) => subcmd.dispatch(),
             #[cfg(enarx_with_shim)]
             Self::Key(subcmd) => subcmd.dispatch(),
+            #[cfg(enarx_with_shim)]
+            Self::Measure(cmd) => cmd.execute(),
             Self::Platform(subcmd) => subcmd.dispatch(),
             Self::Package(subcmd) => subcmd.dispatch(),
             Self::Repo(subcmd) => subcmd.dispatch(),
-            #[cfg(enarx_with_shim)]
-            Self::Sign(cmd) => cmd.execute(),
             Self::Tree(subcmd) => subcmd.dispatch(),
             Self::User(subcmd) => subcmd.dispatch(),
             Self::Unstable(subcmd) => subcmd.dispatch(),
@@ -107,16 +129,19 @@ impl Subcommands {
 /// Common backend and shim options
 #[derive(Args, Debug)]
 pub struct BackendOptions {
-    /// Set which backend to use
+    /// Set which backend to use, by default the configured one or else the
+    /// first one available
     #[clap(long, env = "ENARX_BACKEND")]
     backend: Option<String>,
-    // TODO: Path to an external shim binary?
-    //shim: Option<PathBuf>,
+
+    /// Path of an external shim binary to use instead of the builtin one
+    #[clap(long, env = "ENARX_SHIM", value_name = "SHIM")]
+    shim: Option<Utf8PathBuf>,
 }
 
 impl BackendOptions {
     pub fn pick(&self) -> anyhow::Result<&dyn Backend> {
-        if let Some(ref name) = self.backend {
+        if let Some(name) = self.backend.as_deref().or_else(|| HostConfig::get().backend()) {
             match BACKENDS.deref().iter().find(|b| b.name() == name) {
                 None => {
                     bail!("Keep backend identifier {:?} is unknown.", name)
@@ -140,6 +165,115 @@ impl BackendOptions {
         }
         .map(|b| &**b)
     }
+
+    /// Load the shim for `backend`, either the one given with `--shim` or the
+    /// builtin one.
+    ///
+    /// The notes of an external shim are checked against the host, so that an
+    /// incompatible shim is rejected before building the Keep.
+    pub fn shim(&self, backend: &dyn Backend) -> anyhow::Result<Cow<'static, [u8]>> {
+        let path = match &self.shim {
+            None => return Ok(Cow::Borrowed(backend.shim())),
+            Some(path) => path,
+        };
+        if backend.shim().is_empty() {
+            bail!(
+                "Keep backend {:?} does not use a shim, `--shim` is not supported.",
+                backend.name()
+            )
+        }
+        let shim = fs::read(path).with_context(|| format!("Failed to read shim `{path}`"))?;
+        #[cfg(enarx_with_shim)]
+        crate::backend::check_shim(&shim)
+            .with_context(|| format!("Shim `{path}` is incompatible with this host"))?;
+        Ok(Cow::Owned(shim))
+    }
+}
+
+/// Load the exec for `backend`, either the one at `path` or the builtin one.
+fn load_exec(path: Option<&Utf8Path>, backend: &dyn Backend) -> anyhow::Result<Cow<'static, [u8]>> {
+    if let Some(path) = path {
+        if backend.shim().is_empty() {
+            bail!(
+                "Keep backend {:?} does not use an exec, `--exec` is not supported.",
+                backend.name()
+            )
+        }
+        let exec = fs::read(path).with_context(|| format!("Failed to read exec `{path}`"))?;
+        return Ok(Cow::Owned(exec));
+    }
+    EXECS
+        .iter()
+        .find(|w| w.with_backend(backend))
+        .ok_or_else(|| anyhow!("no supported exec found"))
+        .map(|b| Cow::Borrowed(b.exec()))
+}
+
+/// Identity of a Keep chosen when signing and measuring it
+#[cfg(enarx_with_shim)]
+#[derive(Args, Debug)]
+pub struct IdentityOptions {
+    /// SGX ISV product ID
+    #[clap(long, value_name = "ID")]
+    sgx_isv_prod_id: Option<u16>,
+
+    /// SGX ISV security version number, to be increased on security fixes
+    #[clap(long, value_name = "SVN")]
+    sgx_isv_svn: Option<u16>,
+
+    /// SEV-SNP guest policy, e.g. `0x30000`
+    #[clap(long, value_name = "POLICY", parse(try_from_str = parse_u64))]
+    sev_policy: Option<u64>,
+
+    /// SEV-SNP family ID as 16 hex-encoded bytes
+    #[clap(long, value_name = "HEX", parse(try_from_str = parse_id))]
+    sev_family_id: Option<[u8; 16]>,
+
+    /// SEV-SNP image ID as 16 hex-encoded bytes
+    #[clap(long, value_name = "HEX", parse(try_from_str = parse_id))]
+    sev_image_id: Option<[u8; 16]>,
+
+    /// SEV-SNP guest security version number, to be increased on security fixes
+    #[clap(long, value_name = "SVN")]
+    sev_guest_svn: Option<u32>,
+}
+
+#[cfg(enarx_with_shim)]
+impl IdentityOptions {
+    pub fn identity(&self) -> Identity {
+        Identity {
+            isv_prod_id: self.sgx_isv_prod_id,
+            isv_svn: self.sgx_isv_svn,
+            policy: self.sev_policy,
+            family_id: self.sev_family_id,
+            image_id: self.sev_image_id,
+            guest_svn: self.sev_guest_svn,
+        }
+    }
+}
+
+/// Parse a decimal or `0x`-prefixed hexadecimal integer.
+#[cfg(enarx_with_shim)]
+fn parse_u64(s: &str) -> anyhow::Result<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .with_context(|| format!("Invalid integer `{s}`"))
+}
+
+/// Parse a hex-encoded 16-byte SEV-SNP family or image ID.
+#[cfg(enarx_with_shim)]
+fn parse_id(s: &str) -> anyhow::Result<[u8; 16]> {
+    if s.len() != 32 || !s.is_ascii() {
+        bail!("ID `{s}` must be 32 hexadecimal digits");
+    }
+    let mut id = [0; 16];
+    for (i, b) in id.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[2 * i..][..2], 16)
+            .with_context(|| format!("Invalid hexadecimal ID `{s}`"))?;
+    }
+    Ok(id)
 }
 
 /// Common logging / output options
@@ -159,22 +293,42 @@ pub struct LogOptions {
     #[clap(long = "log-filter", env = "ENARX_LOG")]
     log_filter: Option<String>,
 
-    /// Set log output target ("stderr", "stdout")
-    #[clap(long, default_value = "stderr")]
+    /// Set log output target ("stderr", "stdout", "file:<path>", "syslog", "journald")
+    #[clap(long, default_value = HostConfig::get().log_target())]
     log_target: LogTarget,
+
+    /// Set log format ("text", "json")
+    ///
+    /// JSON records include the backend, Keep ID and package of the Keep.
+    /// Records sent to journald are always structured.
+    #[clap(long, default_value = HostConfig::get().log_format())]
+    log_format: LogFormat,
+
+    /// Size in bytes at which a log file target is rotated
+    #[clap(long, value_name = "BYTES", default_value_t = logging::DEFAULT_MAX_SIZE)]
+    log_max_size: u64,
 }
 
 impl LogOptions {
     /// Build & initialize a global logger using env_logger::Builder.
     /// As with Builder::init(), this will panic if called more than once,
     /// or if another library has already initialized a global logger.
-    pub fn init(&self) {
+    pub fn init(&self) -> anyhow::Result<()> {
         let mut builder = env_logger::Builder::new();
-        builder
-            .filter_level(self.verbosity_level())
-            .parse_filters(self.log_filter.as_ref().unwrap_or(&"".to_owned()))
-            .target(self.log_target.into())
-            .init();
+        builder.filter_level(self.verbosity_level()).parse_filters(
+            self.log_filter
+                .as_deref()
+                .or_else(|| HostConfig::get().log_filter())
+                .unwrap_or_default(),
+        );
+        logging::configure(
+            &mut builder,
+            &self.log_target,
+            self.log_format,
+            self.log_max_size,
+        )?;
+        builder.init();
+        Ok(())
     }
 
     /// Convert the -vvv.. count into a log level.
@@ -189,43 +343,19 @@ impl LogOptions {
     }
 }
 
-/// Represents targets for debug logging.
-/// This will probably grow over time.
-#[derive(Debug, Clone, Copy)]
-#[non_exhaustive]
-enum LogTarget {
-    Stdout,
-    Stderr,
-    // FUTURE: file path, syslog/journal, ...
-}
-
-/// Convert a str to a LogTarget. This is how Clap parses CLI args.
-impl FromStr for LogTarget {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
-        match s.to_ascii_lowercase().as_str() {
-            "stdout" => Ok(Self::Stdout),
-            "stderr" => Ok(Self::Stderr),
-            _ => Err(anyhow!("unknown log target {:?}", s)),
-        }
-    }
-}
-
-/// Convert our LogTarget to env_logger's Target
-impl From<LogTarget> for env_logger::Target {
-    fn from(t: LogTarget) -> Self {
-        match t {
-            LogTarget::Stdout => Self::Stdout,
-            LogTarget::Stderr => Self::Stderr,
-        }
-    }
-}
-
-fn get_signatures(path: Option<Utf8PathBuf>) -> anyhow::Result<Option<Signatures>> {
+/// Load the signatures passed with `--signatures`, or else those configured for `backend`.
+fn get_signatures(
+    path: Option<Utf8PathBuf>,
+    backend: &dyn Backend,
+) -> anyhow::Result<Option<Signatures>> {
+    let path = path
+        .map(Utf8PathBuf::into_std_path_buf)
+        .or_else(|| HostConfig::get().signatures(backend.name()).map(Into::into));
     match path {
         None => Ok(None),
         Some(path) => {
-            let mut file = File::open(path).context("Failed to open hashes file")?;
+            let mut file = File::open(&path)
+                .with_context(|| format!("Failed to open signatures file `{}`", path.display()))?;
             let mut buffer = String::new();
             file.read_to_string(&mut buffer)?;
             serde_json::from_str::<Signatures>(&buffer)
@@ -234,3 +364,74 @@ fn get_signatures(path: Option<Utf8PathBuf>) -> anyhow::Result<Option<Signatures
         }
     }
 }
+
+/// Parse a size in bytes with an optional binary suffix, e.g. `512M` or `2G`.
+fn parse_memory(s: &str) -> anyhow::Result<u64> {
+    let (digits, shift) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 30),
+        Some((i, 'T' | 't')) => (&s[..i], 40),
+        _ => (s, 0),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .filter(|&n| n > 0)
+        .ok_or_else(|| anyhow!("Invalid memory size `{s}`"))
+}
+
+/// The memory limit passed with `--memory`, or else the configured one.
+fn get_memory(memory: Option<u64>) -> anyhow::Result<Option<u64>> {
+    match (memory, HostConfig::get().memory()) {
+        (Some(memory), _) => Ok(Some(memory)),
+        (None, Some(memory)) => parse_memory(memory)
+            .context("Failed to parse `memory` in the host configuration")
+            .map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Encode `bytes` as lowercase hexadecimal.
+#[cfg(enarx_with_shim)]
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(enarx_with_shim)]
+    #[test]
+    fn identity() {
+        use super::{hex, parse_id, parse_u64};
+
+        assert_eq!(parse_u64("0x30000").unwrap(), 0x30000);
+        assert_eq!(parse_u64("196608").unwrap(), 0x30000);
+        assert!(parse_u64("0xfoo").is_err());
+
+        let id = "000102030405060708090a0b0c0d0e0f";
+        assert_eq!(hex(&parse_id(id).unwrap()), id);
+        assert!(parse_id("0001").is_err());
+        assert!(parse_id("zz0102030405060708090a0b0c0d0e0f").is_err());
+    }
+
+    #[test]
+    fn memory() {
+        use super::parse_memory;
+
+        assert_eq!(parse_memory("4096").unwrap(), 4096);
+        assert_eq!(parse_memory("512M").unwrap(), 512 << 20);
+        assert_eq!(parse_memory("2G").unwrap(), 2 << 30);
+        assert_eq!(parse_memory("1t").unwrap(), 1 << 40);
+        assert!(parse_memory("0").is_err());
+        assert!(parse_memory("G").is_err());
+        assert!(parse_memory("2GB").is_err());
+        assert!(parse_memory("99999999T").is_err());
+    }
+}
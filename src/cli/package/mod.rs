@@ -3,6 +3,8 @@
 mod fetch;
 mod info;
 mod publish;
+mod serve;
+mod verify;
 mod yank;
 
 use clap::Subcommand;
@@ -14,6 +16,8 @@ pub enum Subcommands {
     #[clap(hide = true)]
     Fetch(fetch::Options),
     Publish(publish::Options),
+    Serve(serve::Options),
+    Verify(verify::Options),
     #[clap(hide = true)]
     Yank(yank::Options),
 }
@@ -24,6 +28,8 @@ impl Subcommands {
             Self::Info(cmd) => cmd.execute(),
             Self::Fetch(cmd) => cmd.execute(),
             Self::Publish(cmd) => cmd.execute(),
+            Self::Serve(cmd) => cmd.execute(),
+            Self::Verify(cmd) => cmd.execute(),
             Self::Yank(cmd) => cmd.execute(),
         }
     }
@@ -1,26 +1,39 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::drawbridge::{client, TagSpec};
+use crate::drawbridge::{client, sign_entry, Ignore, SigningKey, TagSpec, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
 
 use std::ffi::OsString;
-use std::fs::read_dir;
+use std::fs::{copy, create_dir, read_dir};
+use std::path::Path;
 
 use anyhow::{bail, Context};
 use camino::Utf8PathBuf;
 use clap::Args;
+use drawbridge_client::types::{Tree, TreeContent, TreeEntry};
+use enarx_exec_wasmtime::PACKAGE_ENTRYPOINT;
 use oauth2::url::Url;
+use tempfile::tempdir;
 
 /// Publish a new package.
+///
+/// Paths matching a pattern in the `.enarxignore` file at the root of the
+/// package directory are not published.
 #[derive(Args, Debug)]
 pub struct Options {
     #[clap(long, env = "ENARX_CA_BUNDLE")]
     ca_bundle: Option<Utf8PathBuf>,
-    #[clap(long, default_value = "https://auth.profian.com/")]
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
     oidc_domain: Url,
     #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
     insecure_auth_token: Option<String>,
     #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
     credential_helper: Option<OsString>,
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
+    /// Sign the package with the PKCS#8 P-256, P-384 or Ed25519 private key at this path.
+    #[clap(long, value_name = "KEY")]
+    sign_key: Option<Utf8PathBuf>,
     spec: TagSpec,
     path: Utf8PathBuf,
 }
@@ -30,35 +43,82 @@ impl Options {
         let cl = client(
             &self.spec.host,
             &self.oidc_domain,
+            &self.profile,
             &self.insecure_auth_token,
             &self.ca_bundle,
             &self.credential_helper,
         )?;
 
         // TODO: this logic should live in Drawbridge, so that it can be reused for the server
+        let staged = tempdir().context("Failed to create staging directory")?;
         if self.path.is_file() {
             self.path
                 .file_name()
-                .filter(|&name| name == "main.wasm")
+                .filter(|&name| name == PACKAGE_ENTRYPOINT)
                 .with_context(|| format!("Invalid file name: {}", self.path))?;
+            copy(&self.path, staged.path().join(PACKAGE_ENTRYPOINT))
+                .with_context(|| format!("Failed to copy `{}`", self.path))?;
         } else {
-            for entry in read_dir(self.path.clone())? {
-                let path = entry?.path();
-                if path.is_file() {
-                    path.file_name()
-                        .filter(|&name| name == "main.wasm" || name == "Enarx.toml")
-                        .with_context(|| format!("Invalid file name: {}", path.display()))?;
-                } else {
-                    bail!("Publishing nested directories is not supported")
-                }
+            if !self.path.join(PACKAGE_ENTRYPOINT).is_file() {
+                bail!("Package directory must contain a `{PACKAGE_ENTRYPOINT}` file")
             }
+            let ignore = Ignore::load(&self.path)?;
+            stage(&ignore, self.path.as_std_path(), staged.path(), "")?;
         }
 
         let tag = cl.tag(&self.spec.ctx);
-        let (_tag_created, _tree_created) = tag
-            .create_from_path_unsigned(self.path)
-            .context("Failed to create tag and upload tree")?;
+        if let Some(ref sign_key) = self.sign_key {
+            let key = SigningKey::load(sign_key)?;
+            let tree =
+                Tree::from_path_sync(staged.path()).context("Failed to read package tree")?;
+            let entry = sign_entry(&key, &tree.root())?;
+            tag.create(&entry).context("Failed to create signed tag")?;
+            for (path, TreeEntry { meta, content, .. }) in tree {
+                let node = tag.path(&path);
+                match content {
+                    TreeContent::File(file) => node.create_from(&meta, file),
+                    TreeContent::Directory(buf) => node.create_from(&meta, buf.as_slice()),
+                }
+                .with_context(|| format!("Failed to upload tree node: {path}"))?;
+            }
+        } else {
+            let (_tag_created, _tree_created) = tag
+                .create_from_path_unsigned(staged.path())
+                .context("Failed to create tag and upload tree")?;
+        }
 
         Ok(())
     }
 }
+
+/// Copy the contents of the directory `src` to `dst`, skipping paths excluded by `ignore`.
+///
+/// `prefix` is the `/`-separated path of `src` relative to the package root.
+fn stage(ignore: &Ignore, src: &Path, dst: &Path, prefix: &str) -> anyhow::Result<()> {
+    for entry in read_dir(src).with_context(|| format!("Failed to read `{}`", src.display()))? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("Invalid file name: {}", path.display()))?;
+        let rel = format!("{prefix}{name}");
+        if path.is_dir() {
+            if ignore.excludes(&rel, true) {
+                continue;
+            }
+            let dst = dst.join(name);
+            create_dir(&dst)
+                .with_context(|| format!("Failed to create directory `{}`", dst.display()))?;
+            stage(ignore, &path, &dst, &format!("{rel}/"))?;
+        } else if path.is_file() {
+            if ignore.excludes(&rel, false) {
+                continue;
+            }
+            copy(&path, dst.join(name))
+                .with_context(|| format!("Failed to copy `{}`", path.display()))?;
+        } else {
+            bail!("Unsupported file type: {}", path.display())
+        }
+    }
+    Ok(())
+}
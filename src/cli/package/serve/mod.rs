@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: Apache-2.0
+
+mod tls;
+
+use crate::drawbridge::Ignore;
+
+use std::fs::{self, read_dir};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{bail, Context};
+use camino::Utf8PathBuf;
+use clap::Args;
+use ring::digest::{digest, Algorithm, SHA256, SHA384};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde_json::{json, Map, Value};
+
+const API_PREFIX: &str = "/api/v0.1.0/";
+const DIRECTORY_TYPE: &str = "application/vnd.drawbridge.directory.v1+json";
+
+/// Serve local package directories to `enarx deploy` for offline development.
+///
+/// Packages are looked up at `<DIR>/<user>/<repo>/<tag>/` and served using the
+/// package host API layout, so that e.g. `<DIR>/alice/app/0.1.0/` can be
+/// deployed as `localhost:8080/alice/app:0.1.0`.
+/// Paths matching a pattern in the `.enarxignore` file at the root of the
+/// package directory are not served.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Address to listen on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Serve over TLS using a certificate issued by an ephemeral CA and
+    /// write the PEM-encoded CA certificate to this path.
+    #[clap(long, value_name = "PATH")]
+    tls_ca: Option<Utf8PathBuf>,
+    dir: Utf8PathBuf,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        if !self.dir.is_dir() {
+            bail!("`{}` is not a directory", self.dir)
+        }
+
+        let tls = match self.tls_ca {
+            Some(ref path) => {
+                let (ca, config) = tls::generate()?;
+                fs::write(path, ca)
+                    .with_context(|| format!("Failed to write CA certificate to `{path}`"))?;
+                Some(config)
+            }
+            None => None,
+        };
+
+        let listener = TcpListener::bind(self.listen)
+            .with_context(|| format!("Failed to listen on `{}`", self.listen))?;
+        let scheme = if tls.is_some() { "https" } else { "http" };
+        eprintln!(
+            "Serving packages from `{}` at {scheme}://{}",
+            self.dir,
+            listener.local_addr()?
+        );
+
+        let root: Arc<Path> = self.dir.into_std_path_buf().into();
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept connection: {e}");
+                    continue;
+                }
+            };
+            let root = root.clone();
+            let tls = tls.clone();
+            thread::spawn(move || {
+                if let Err(e) = connection(&root, tls, stream) {
+                    eprintln!("Failed to handle request: {e:#}");
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+fn connection(
+    root: &Path,
+    tls: Option<Arc<ServerConfig>>,
+    stream: TcpStream,
+) -> anyhow::Result<()> {
+    match tls {
+        Some(config) => {
+            let conn = ServerConnection::new(config)?;
+            let mut stream = StreamOwned::new(conn, stream);
+            handle(root, &mut stream)?;
+            stream.conn.send_close_notify();
+            stream.flush()?;
+            Ok(())
+        }
+        None => handle(root, &mut &stream),
+    }
+}
+
+/// Handle a single HTTP/1.1 request on `stream`.
+fn handle(root: &Path, stream: &mut (impl Read + Write)) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(&mut *stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut request = line.split_whitespace();
+    let (method, target) = match (request.next(), request.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return respond(stream, "400 Bad Request", None, false),
+    };
+    // Requests never carry a body, so the headers can be discarded.
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let head = match method.as_str() {
+        "GET" => false,
+        "HEAD" => true,
+        _ => return respond(stream, "405 Method Not Allowed", None, false),
+    };
+    let path = target.split('?').next().unwrap_or_default();
+    match route(root, path)? {
+        Some(node) => respond(stream, "200 OK", Some(node), head),
+        None => respond(stream, "404 Not Found", None, head),
+    }
+}
+
+fn respond(
+    stream: &mut impl Write,
+    status: &str,
+    node: Option<(&str, Vec<u8>)>,
+    head: bool,
+) -> anyhow::Result<()> {
+    let (typ, body) = node.unwrap_or(("text/plain", status.as_bytes().to_vec()));
+    let mut headers = format!(
+        "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: {typ}\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    if status == "200 OK" {
+        headers.push_str(&format!(
+            "Content-Digest: sha-256=:{}:\r\n",
+            base64::encode(digest(&SHA256, &body))
+        ));
+    }
+    headers.push_str("\r\n");
+    stream.write_all(headers.as_bytes())?;
+    if !head {
+        stream.write_all(&body)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Resolve `/api/v0.1.0/<user>/<repo>/_tag/<tag>/tree[/<path>]` to a node of a package.
+fn route(root: &Path, path: &str) -> anyhow::Result<Option<(&'static str, Vec<u8>)>> {
+    let segments: Vec<_> = match path.strip_prefix(API_PREFIX) {
+        Some(path) => path.trim_end_matches('/').split('/').collect(),
+        None => return Ok(None),
+    };
+    if segments
+        .iter()
+        .any(|&s| s.is_empty() || s == "." || s == "..")
+    {
+        return Ok(None);
+    }
+    let (package, path) = match segments.as_slice() {
+        [user, repo, "_tag", tag, "tree", path @ ..] => {
+            (root.join(user).join(repo).join(tag), path)
+        }
+        _ => return Ok(None),
+    };
+    if !package.is_dir() {
+        return Ok(None);
+    }
+
+    let ignore = Ignore::load(&package)?;
+    for i in 1..=path.len() {
+        let rel = path[..i].join("/");
+        if ignore.excludes(&rel, package.join(&rel).is_dir()) {
+            return Ok(None);
+        }
+    }
+    node(&ignore, &package, &path.join("/"))
+}
+
+/// Returns the media type and body of the node at `rel` within the package at `package`.
+fn node(
+    ignore: &Ignore,
+    package: &Path,
+    rel: &str,
+) -> anyhow::Result<Option<(&'static str, Vec<u8>)>> {
+    let path = package.join(rel);
+    let meta = match fs::metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to stat `{}`", path.display())),
+    };
+
+    if meta.is_file() {
+        let typ = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wasm") => "application/wasm",
+            Some("toml") => "application/toml",
+            _ => "application/octet-stream",
+        };
+        let body =
+            fs::read(&path).with_context(|| format!("Failed to read `{}`", path.display()))?;
+        return Ok(Some((typ, body)));
+    } else if !meta.is_dir() {
+        return Ok(None);
+    }
+
+    let mut listing = Map::new();
+    for entry in read_dir(&path).with_context(|| format!("Failed to read `{}`", path.display()))? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(name) => bail!("Invalid file name: {name:?}"),
+        };
+        let child = if rel.is_empty() {
+            name.clone()
+        } else {
+            format!("{rel}/{name}")
+        };
+        if ignore.excludes(&child, entry.path().is_dir()) {
+            continue;
+        }
+        if let Some((typ, body)) = node(ignore, package, &child)? {
+            let hash = |alg: &'static Algorithm| Value::from(base64::encode(digest(alg, &body)));
+            listing.insert(
+                name,
+                json!({
+                    "digest": { "sha-256": hash(&SHA256), "sha-384": hash(&SHA384) },
+                    "length": body.len(),
+                    "type": typ,
+                }),
+            );
+        }
+    }
+    Ok(Some((DIRECTORY_TYPE, serde_json::to_vec(&listing)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        let root = tempfile::tempdir().unwrap();
+        let package = root.path().join("alice/app/0.1.0");
+        fs::create_dir_all(package.join("model")).unwrap();
+        fs::write(package.join("main.wasm"), b"wasm").unwrap();
+        fs::write(package.join("model/weights.bin"), b"0123").unwrap();
+        fs::write(package.join("debug.log"), b"log").unwrap();
+        fs::write(package.join(".enarxignore"), b"*.log\n").unwrap();
+
+        let get = |path: &str| route(root.path(), path).unwrap();
+        let tree = "/api/v0.1.0/alice/app/_tag/0.1.0/tree";
+
+        let (typ, body) = get(tree).unwrap();
+        assert_eq!(typ, DIRECTORY_TYPE);
+        let listing: Map<String, Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listing.keys().collect::<Vec<_>>(), ["main.wasm", "model"]);
+        assert_eq!(listing["main.wasm"]["type"], "application/wasm");
+        assert_eq!(listing["main.wasm"]["length"], 4);
+
+        assert_eq!(
+            get(&format!("{tree}/model/weights.bin")),
+            Some(("application/octet-stream", b"0123".to_vec()))
+        );
+        assert_eq!(get(&format!("{tree}/debug.log")), None);
+        assert_eq!(get(&format!("{tree}/.enarxignore")), None);
+        assert_eq!(get(&format!("{tree}/../../../0.1.0/tree")), None);
+        assert_eq!(get("/api/v0.1.0/alice/app/_tag/0.2.0/tree"), None);
+    }
+}
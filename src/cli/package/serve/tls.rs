@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ephemeral CA and server certificate generation.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use const_oid::db::rfc5280::{
+    ID_CE_BASIC_CONSTRAINTS, ID_CE_EXT_KEY_USAGE, ID_CE_KEY_USAGE, ID_CE_SUBJECT_ALT_NAME,
+    ID_KP_SERVER_AUTH,
+};
+use const_oid::db::rfc5912::{ECDSA_WITH_SHA_256, ID_EC_PUBLIC_KEY, SECP_256_R_1};
+use pkcs8::{AlgorithmIdentifier, ObjectIdentifier, SubjectPublicKeyInfo};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use x509_cert::der::asn1::{BitStringRef, Ia5StringRef, UIntRef};
+use x509_cert::der::{Decode, Encode};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{
+    BasicConstraints, ExtendedKeyUsage, KeyUsage, KeyUsages, SubjectAltName,
+};
+use x509_cert::ext::Extension;
+use x509_cert::name::RdnSequence;
+use x509_cert::time::Validity;
+use x509_cert::{TbsCertificate, Version};
+
+const CA_NAME: &str = "CN=Enarx Package Server CA";
+const SERVER_NAME: &str = "CN=localhost";
+
+const ES256: AlgorithmIdentifier<'static> = AlgorithmIdentifier {
+    oid: ECDSA_WITH_SHA_256,
+    parameters: None,
+};
+
+/// Validity of the generated certificates
+const VALIDITY: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+fn generate_key(rng: &SystemRandom) -> anyhow::Result<(Vec<u8>, EcdsaKeyPair)> {
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, rng)
+        .map_err(|_| anyhow!("failed to generate key"))?;
+    let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref())
+        .map_err(|_| anyhow!("failed to parse generated key"))?;
+    Ok((pkcs8.as_ref().to_vec(), key))
+}
+
+/// Create a DER-encoded certificate for `key` signed by `issuer_key`.
+fn certificate(
+    rng: &SystemRandom,
+    subject: &str,
+    key: &EcdsaKeyPair,
+    issuer: &str,
+    issuer_key: &EcdsaKeyPair,
+    extensions: &[(ObjectIdentifier, bool, Vec<u8>)],
+) -> anyhow::Result<Vec<u8>> {
+    let subject = RdnSequence::encode_from_string(subject)?;
+    let issuer = RdnSequence::encode_from_string(issuer)?;
+
+    let mut serial = [0u8; 16];
+    rng.fill(&mut serial)
+        .map_err(|_| anyhow!("failed to generate serial number"))?;
+
+    let tbs = TbsCertificate {
+        version: Version::V3,
+        serial_number: UIntRef::new(&serial)?,
+        signature: ES256,
+        issuer: RdnSequence::from_der(&issuer)?,
+        validity: Validity::from_now(VALIDITY)?,
+        subject: RdnSequence::from_der(&subject)?,
+        subject_public_key_info: SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier {
+                oid: ID_EC_PUBLIC_KEY,
+                parameters: Some((&SECP_256_R_1).into()),
+            },
+            subject_public_key: key.public_key().as_ref(),
+        },
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(
+            extensions
+                .iter()
+                .map(|(extn_id, critical, extn_value)| Extension {
+                    extn_id: *extn_id,
+                    critical: *critical,
+                    extn_value,
+                })
+                .collect(),
+        ),
+    };
+
+    let sig = issuer_key
+        .sign(rng, &tbs.to_vec()?)
+        .map_err(|_| anyhow!("failed to sign certificate"))?;
+    let crt = x509_cert::Certificate {
+        tbs_certificate: tbs,
+        signature_algorithm: ES256,
+        signature: BitStringRef::from_bytes(sig.as_ref())?,
+    };
+    Ok(crt.to_vec()?)
+}
+
+/// Generate an ephemeral CA and a certificate for `localhost` issued by it.
+///
+/// Returns the PEM-encoded CA certificate and a server configuration using
+/// the `localhost` certificate.
+pub fn generate() -> anyhow::Result<(String, Arc<ServerConfig>)> {
+    let rng = SystemRandom::new();
+    let (_, ca_key) = generate_key(&rng)?;
+    let (server_pkcs8, server_key) = generate_key(&rng)?;
+
+    let ca = certificate(
+        &rng,
+        CA_NAME,
+        &ca_key,
+        CA_NAME,
+        &ca_key,
+        &[
+            (
+                ID_CE_KEY_USAGE,
+                true,
+                KeyUsage(KeyUsages::KeyCertSign | KeyUsages::CRLSign).to_vec()?,
+            ),
+            (
+                ID_CE_BASIC_CONSTRAINTS,
+                true,
+                BasicConstraints {
+                    ca: true,
+                    path_len_constraint: Some(0),
+                }
+                .to_vec()?,
+            ),
+        ],
+    )
+    .context("failed to generate CA certificate")?;
+
+    let server = certificate(
+        &rng,
+        SERVER_NAME,
+        &server_key,
+        CA_NAME,
+        &ca_key,
+        &[
+            (
+                ID_CE_KEY_USAGE,
+                true,
+                KeyUsage(KeyUsages::DigitalSignature.into()).to_vec()?,
+            ),
+            (
+                ID_CE_EXT_KEY_USAGE,
+                false,
+                ExtendedKeyUsage(vec![ID_KP_SERVER_AUTH]).to_vec()?,
+            ),
+            (
+                ID_CE_SUBJECT_ALT_NAME,
+                false,
+                SubjectAltName(vec![GeneralName::DnsName(Ia5StringRef::new("localhost")?)])
+                    .to_vec()?,
+            ),
+        ],
+    )
+    .context("failed to generate server certificate")?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(server), Certificate(ca.clone())],
+            PrivateKey(server_pkcs8),
+        )
+        .context("failed to construct TLS configuration")?;
+
+    let pem = base64::encode(&ca)
+        .as_bytes()
+        .chunks(64)
+        .map(|line| format!("{}\n", String::from_utf8_lossy(line)))
+        .collect::<String>();
+    Ok((
+        format!("-----BEGIN CERTIFICATE-----\n{pem}-----END CERTIFICATE-----\n"),
+        Arc::new(config),
+    ))
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::drawbridge::{client, verify_entry, TagSpec, VerifyingKey, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
+
+use std::ffi::OsString;
+
+use anyhow::Context;
+use camino::Utf8PathBuf;
+use clap::Args;
+use oauth2::url::Url;
+
+/// Verify the signature of a published package against trusted publisher keys.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(long, env = "ENARX_CA_BUNDLE")]
+    ca_bundle: Option<Utf8PathBuf>,
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
+    oidc_domain: Url,
+    #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
+    insecure_auth_token: Option<String>,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
+    /// PEM-encoded public key of a trusted publisher. May be passed multiple times.
+    #[clap(long = "trusted-key", value_name = "PUBLIC_KEY", required = true)]
+    trusted_keys: Vec<Utf8PathBuf>,
+    spec: TagSpec,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let trusted = self
+            .trusted_keys
+            .iter()
+            .map(VerifyingKey::load)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let cl = client(
+            &self.spec.host,
+            &self.oidc_domain,
+            &self.profile,
+            &self.insecure_auth_token,
+            &self.ca_bundle,
+            &self.credential_helper,
+        )?;
+        let tag_entry = cl
+            .tag(&self.spec.ctx)
+            .get()
+            .context("Failed to retrieve package information")?;
+        verify_entry(&tag_entry, &trusted)?;
+
+        println!("Package signature verified.");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::backend::{Datum, Severity, BACKENDS};
+use crate::host_config::HostConfig;
+
+use std::ops::Deref;
+
+use anyhow::anyhow;
+use clap::Args;
+use serde::Serialize;
+
+/// Exit status if a required check fails.
+const EXIT_REQUIRED: i32 = 2;
+
+/// Exit status if only recommended checks fail.
+const EXIT_RECOMMENDED: i32 = 3;
+
+/// Check whether a backend is ready for use on this system
+///
+/// Exits with status 0 if all checks pass, 2 if a required check fails and 3
+/// if only recommended checks fail. The IDs of the failing checks are printed
+/// on the standard error.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Backend to check, by default the configured one
+    #[clap(long, env = "ENARX_BACKEND")]
+    backend: Option<String>,
+
+    /// Emit JSON rather than human-readable output
+    #[clap(short, long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct Report {
+    backend: &'static str,
+    ready: bool,
+    failed: Vec<&'static str>,
+    data: Vec<Datum>,
+}
+
+impl Report {
+    fn exit_code(&self) -> i32 {
+        if self.data.iter().any(Datum::fails) {
+            EXIT_REQUIRED
+        } else if !self.ready {
+            EXIT_RECOMMENDED
+        } else {
+            0
+        }
+    }
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let name = self
+            .backend
+            .as_deref()
+            .or_else(|| HostConfig::get().backend())
+            .ok_or_else(|| anyhow!("No backend to check, please pass `--backend`"))?;
+        let backend = BACKENDS
+            .deref()
+            .iter()
+            .find(|b| b.name() == name)
+            .ok_or_else(|| anyhow!("Keep backend identifier {:?} is unknown.", name))?;
+
+        let mut data = backend.data();
+        data.extend(backend.config());
+        let failed: Vec<_> = data.iter().filter(|d| !d.pass).map(|d| d.id).collect();
+        let report = Report {
+            backend: backend.name(),
+            ready: failed.is_empty(),
+            failed,
+            data,
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            for datum in &report.data {
+                let name = datum.name.trim();
+                match (datum.pass, datum.severity) {
+                    (true, _) => println!("ok {}: {name}", datum.id),
+                    (false, severity) => {
+                        let status = match severity {
+                            Severity::Required => "FAILED",
+                            Severity::Recommended => "WARNING",
+                        };
+                        println!("{status} {} [{}]: {name}", datum.id, datum.remediation);
+                        if let Some(mesg) = datum.mesg.as_ref() {
+                            println!("  {mesg}");
+                        }
+                    }
+                }
+            }
+        }
+
+        let code = report.exit_code();
+        if code != 0 {
+            eprintln!(
+                "Backend `{}` is not ready, failed checks: {}",
+                report.backend,
+                report.failed.join(", ")
+            );
+            std::process::exit(code);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_exit_code() {
+    use crate::backend::Remediation;
+
+    let datum = |pass, severity| Datum {
+        id: "test",
+        name: "Test".into(),
+        pass,
+        severity,
+        remediation: Remediation::Kernel,
+        info: None,
+        mesg: None,
+    };
+    let report = |data: Vec<Datum>| Report {
+        backend: "test",
+        ready: data.iter().all(|d| d.pass),
+        failed: vec![],
+        data,
+    };
+
+    assert_eq!(report(vec![datum(true, Severity::Required)]).exit_code(), 0);
+    assert_eq!(
+        report(vec![datum(false, Severity::Recommended)]).exit_code(),
+        EXIT_RECOMMENDED
+    );
+    assert_eq!(
+        report(vec![
+            datum(false, Severity::Recommended),
+            datum(false, Severity::Required)
+        ])
+        .exit_code(),
+        EXIT_REQUIRED
+    );
+}
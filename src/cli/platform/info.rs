@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::backend::{Backend, BACKENDS};
+use crate::backend::{Backend, Severity, BACKENDS};
 
 use std::fmt::{self, Formatter};
 use std::ops::Deref;
@@ -98,6 +98,13 @@ impl fmt::Display for Info<'_> {
             }
         }
 
+        fn get_warning_icon(is_atty: bool) -> String {
+            match is_atty {
+                true => "!".yellow().to_string(),
+                false => "!".into(),
+            }
+        }
+
         let is_atty = atty::is(atty::Stream::Stdout);
         let backends = self.backends;
 
@@ -113,7 +120,10 @@ impl fmt::Display for Info<'_> {
             writeln!(f, "{} Backend: {}", icon, backend.name())?;
 
             for datum in &data {
-                let icon = get_icon(is_atty, datum.pass);
+                let icon = match (datum.pass, datum.severity) {
+                    (false, Severity::Recommended) => get_warning_icon(is_atty),
+                    (pass, _) => get_icon(is_atty, pass),
+                };
                 write!(f, "  {} {}", icon, datum.name)?;
 
                 if let Some(ref info) = datum.info {
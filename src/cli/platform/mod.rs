@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod check;
 mod info;
 #[cfg(enarx_with_shim)]
 mod sgx;
@@ -12,6 +13,7 @@ use clap::Subcommand;
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     Info(info::Options),
+    Check(check::Options),
     #[cfg(enarx_with_shim)]
     #[clap(subcommand)]
     Sgx(sgx::Subcommands),
@@ -24,6 +26,7 @@ impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<()> {
         match self {
             Self::Info(cmd) => cmd.execute(),
+            Self::Check(cmd) => cmd.execute(),
             #[cfg(enarx_with_shim)]
             Self::Sgx(subcmd) => subcmd.dispatch(),
             #[cfg(enarx_with_shim)]
@@ -1,13 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::cli::{get_signatures, BackendOptions};
-use crate::exec::{open_package, run_package, EXECS};
+use crate::cli::{get_memory, get_signatures, load_exec, logging, parse_memory, BackendOptions};
+use crate::exec::{open_package, run_package, RunOptions};
 
 use std::fmt::Debug;
 #[cfg(unix)]
 use std::os::unix::io::IntoRawFd;
+use std::time::Duration;
 
-use anyhow::anyhow;
 use camino::Utf8PathBuf;
 use clap::Args;
 use enarx_exec_wasmtime::Package;
@@ -29,6 +29,35 @@ pub struct Options {
     #[clap(long, value_name = "SIGNATURES")]
     pub signatures: Option<Utf8PathBuf>,
 
+    /// Path of an external exec binary to use instead of the builtin one
+    #[clap(long, env = "ENARX_EXEC", value_name = "EXEC")]
+    pub exec: Option<Utf8PathBuf>,
+
+    /// Allow sealing keys derived from a public value on backends without
+    /// hardware isolation, such as `nil` or `kvm`.
+    #[clap(long)]
+    pub insecure: bool,
+
+    /// Seconds to wait for the workload to exit after `SIGTERM` or `SIGINT`,
+    /// before the Keep is killed
+    #[clap(
+        long,
+        env = "ENARX_SHUTDOWN_GRACE",
+        value_name = "SECONDS",
+        default_value = "30"
+    )]
+    pub shutdown_grace: u64,
+
+    /// Address to serve the metrics of the Keep on as Prometheus text,
+    /// e.g. `localhost:9100`
+    #[clap(long, env = "ENARX_METRICS_LISTEN", value_name = "ADDR")]
+    pub metrics_listen: Option<String>,
+
+    /// Maximum memory of the Keep, e.g. `2G`, by default the configured one
+    /// or else unlimited. Only supported by the `kvm` and `sev` backends.
+    #[clap(long, env = "ENARX_MEMORY", value_name = "SIZE", parse(try_from_str = parse_memory))]
+    pub memory: Option<u64>,
+
     /// gdb options
     #[cfg(feature = "gdb")]
     #[clap(long, default_value = "localhost:23456")]
@@ -38,21 +67,28 @@ pub struct Options {
 impl Options {
     pub fn execute(self) -> anyhow::Result<()> {
         let Self {
-            backend,
+            backend: backend_options,
             wasmcfgfile,
             module,
             signatures,
+            exec,
+            insecure,
+            shutdown_grace,
+            metrics_listen,
+            memory,
             #[cfg(feature = "gdb")]
             gdblisten,
         } = self;
-        let backend = backend.pick()?;
-        let exec = EXECS
-            .iter()
-            .find(|w| w.with_backend(backend))
-            .ok_or_else(|| anyhow!("no supported exec found"))
-            .map(|b| b.exec())?;
+        let shutdown_grace = Duration::from_secs(shutdown_grace);
+        let memory = get_memory(memory)?;
+        let backend = backend_options.pick()?;
+        let shim = backend_options.shim(backend)?;
+        logging::set_context("backend", backend.name());
+        logging::set_context("keep", logging::keep_id());
+        logging::set_context("package", module.as_str());
+        let exec = load_exec(exec.as_deref(), backend)?;
 
-        let signatures = get_signatures(signatures)?;
+        let signatures = get_signatures(signatures, backend)?;
 
         let get_pkg = || {
             let (wasm, conf) = open_package(module, wasmcfgfile)?;
@@ -69,16 +105,20 @@ impl Options {
             Ok(pkg)
         };
 
-        let code = run_package(
-            backend,
-            exec,
-            signatures,
+        let options = RunOptions {
             #[cfg(not(feature = "gdb"))]
-            None,
+            gdblisten: None,
             #[cfg(feature = "gdb")]
-            Some(gdblisten),
-            get_pkg,
-        )?;
+            gdblisten: Some(gdblisten),
+            ca_bundle: None,
+            insecure_keys: insecure,
+            package_digest: None,
+            shutdown_grace,
+            metrics_listen,
+            memory,
+        };
+
+        let code = run_package(backend, shim, exec, signatures, options, get_pkg)?;
         std::process::exit(code);
     }
 }
@@ -37,6 +37,7 @@ impl Options {
         use mmarinus::{perms, Map, Private};
 
         let backend = self.backend.pick()?;
+        let shim = self.backend.shim(backend)?;
         let binary = Map::load(&self.binpath, Private, perms::Read)?;
 
         #[cfg(not(feature = "gdb"))]
@@ -45,7 +46,7 @@ impl Options {
         #[cfg(feature = "gdb")]
         let gdblisten = Some(self.gdblisten);
 
-        let exit_code = keep_exec(backend, backend.shim(), binary, None, gdblisten)?;
+        let exit_code = keep_exec(backend, shim, binary, None, gdblisten)?;
         std::process::exit(exit_code);
     }
 }
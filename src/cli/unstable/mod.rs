@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod exec;
+mod restore;
+mod snapshot;
 
 use clap::Subcommand;
 
@@ -12,6 +14,8 @@ use clap::Subcommand;
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     Exec(exec::Options),
+    Snapshot(snapshot::Options),
+    Restore(restore::Options),
 }
 
 impl Subcommands {
@@ -21,6 +25,14 @@ impl Subcommands {
             Self::Exec(_) => anyhow::bail!("exec option not supported"),
             #[cfg(enarx_with_shim)]
             Self::Exec(cmd) => cmd.execute(),
+            #[cfg(not(enarx_with_shim))]
+            Self::Snapshot(_) => anyhow::bail!("snapshot option not supported"),
+            #[cfg(enarx_with_shim)]
+            Self::Snapshot(cmd) => cmd.execute(),
+            #[cfg(not(enarx_with_shim))]
+            Self::Restore(_) => anyhow::bail!("restore option not supported"),
+            #[cfg(enarx_with_shim)]
+            Self::Restore(cmd) => cmd.execute(),
         }
     }
 }
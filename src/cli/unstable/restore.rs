@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use camino::Utf8PathBuf;
+use clap::Args;
+
+/// Resume a KVM Keep from the last snapshot taken by `enarx unstable snapshot`.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Snapshot file to restore the Keep from
+    #[clap(value_name = "FILE")]
+    pub snapshot: Utf8PathBuf,
+}
+
+#[cfg(enarx_with_shim)]
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        use crate::backend::kvm::snapshot;
+        use crate::exec::keep_run;
+
+        let keep = snapshot::restore(self.snapshot.as_std_path())?;
+        let exit_code = keep_run(keep, None)?;
+        std::process::exit(exit_code);
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::cli::BackendOptions;
+
+use camino::Utf8PathBuf;
+use clap::Args;
+
+/// Execute a (static, PIE) binary inside a KVM Keep, taking snapshots of it.
+///
+/// The binary is run like with `enarx unstable exec`. Each time the process
+/// receives `SIGUSR1`, a snapshot of the Keep is appended to the output file.
+/// The first snapshot holds all of the guest memory, each later one only the
+/// pages written since the previous one. The Keep can then be resumed from
+/// the last snapshot with `enarx unstable restore`.
+///
+/// Only the plain `kvm` backend supports snapshots. The host resources used
+/// by the binary, like open files and sockets, are not part of a snapshot.
+#[derive(Args, Debug)]
+pub struct Options {
+    #[clap(flatten)]
+    pub backend: BackendOptions,
+
+    /// File to write the snapshots to
+    #[clap(short, long, value_name = "FILE")]
+    pub output: Utf8PathBuf,
+
+    /// Binary to load and run inside the keep
+    #[clap(value_name = "BINARY")]
+    pub binpath: Utf8PathBuf,
+}
+
+#[cfg(enarx_with_shim)]
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        use crate::backend::kvm::snapshot;
+        use crate::exec::keep_exec;
+        use mmarinus::{perms, Map, Private};
+
+        let backend = self.backend.pick()?;
+        if backend.name() != "kvm" {
+            anyhow::bail!(
+                "Keep backend {:?} does not support snapshots, please use `--backend kvm`.",
+                backend.name()
+            )
+        }
+        let shim = self.backend.shim(backend)?;
+        let binary = Map::load(&self.binpath, Private, perms::Read)?;
+
+        snapshot::on_signal(self.output.as_std_path())?;
+        let exit_code = keep_exec(backend, shim, binary, None, None)?;
+        std::process::exit(exit_code);
+    }
+}
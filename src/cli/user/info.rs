@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::drawbridge::{client, UserSpec};
+use crate::drawbridge::{client, UserSpec, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
 
 use std::ffi::OsString;
 
@@ -14,12 +15,14 @@ use oauth2::url::Url;
 pub struct Options {
     #[clap(long, env = "ENARX_CA_BUNDLE")]
     ca_bundle: Option<Utf8PathBuf>,
-    #[clap(long, default_value = "https://auth.profian.com/")]
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
     oidc_domain: Url,
     #[clap(long, env = "ENARX_INSECURE_AUTH_TOKEN")]
     insecure_auth_token: Option<String>,
     #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
     credential_helper: Option<OsString>,
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
     spec: UserSpec,
 }
 
@@ -28,6 +31,7 @@ impl Options {
         let cl = client(
             &self.spec.host,
             &self.oidc_domain,
+            &self.profile,
             &self.insecure_auth_token,
             &self.ca_bundle,
             &self.credential_helper,
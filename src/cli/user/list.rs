@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::drawbridge::{profiles, DEFAULT_PROFILE};
+
+use clap::Args;
+
+/// List the profiles with locally saved credentials.
+///
+/// The currently selected profile is marked with `*`.
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Emit JSON rather than human-readable output
+    #[clap(short, long)]
+    json: bool,
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
+}
+
+impl Options {
+    pub fn execute(self) -> anyhow::Result<()> {
+        let profiles = profiles()?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&profiles)?);
+            return Ok(());
+        }
+
+        for (domain, names) in profiles {
+            println!("{domain}");
+            for name in names {
+                let mark = if name == self.profile { '*' } else { ' ' };
+                println!("  {mark} {name}");
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::drawbridge::login;
+use crate::drawbridge::{login, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
 
 use std::ffi::OsString;
 
@@ -10,12 +11,15 @@ use oauth2::url::Url;
 /// Log in to an Enarx package host and save credentials locally.
 #[derive(Args, Debug)]
 pub struct Options {
-    #[clap(long, default_value = "https://auth.profian.com/")]
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
     oidc_domain: Url,
     #[clap(long, default_value = "4NuaJxkQv8EZBeJKE56R57gKJbxrTLG2")]
     oidc_client_id: String,
     #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
     credential_helper: Option<OsString>,
+    /// Name of the profile to save the credentials under.
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
 }
 
 impl Options {
@@ -24,9 +28,10 @@ impl Options {
             ref oidc_domain,
             oidc_client_id,
             ref credential_helper,
+            ref profile,
         } = self;
 
-        login(oidc_domain, oidc_client_id, credential_helper)?;
+        login(oidc_domain, oidc_client_id, profile, credential_helper)?;
 
         println!("Login successful.");
 
@@ -1,13 +1,42 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::drawbridge::{logout, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
+
+use std::ffi::OsString;
+
 use clap::Args;
+use oauth2::url::Url;
 
 /// Log out of an Enarx package host and delete local credentials.
+///
+/// If the identity provider issued a refresh token, it is revoked as well.
 #[derive(Args, Debug)]
-pub struct Options {}
+pub struct Options {
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
+    oidc_domain: Url,
+    #[clap(long, default_value = "4NuaJxkQv8EZBeJKE56R57gKJbxrTLG2")]
+    oidc_client_id: String,
+    #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
+    credential_helper: Option<OsString>,
+    /// Name of the profile to delete the credentials of.
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
+}
 
 impl Options {
     pub fn execute(self) -> anyhow::Result<()> {
-        unimplemented!()
+        let Self {
+            ref oidc_domain,
+            oidc_client_id,
+            ref credential_helper,
+            ref profile,
+        } = self;
+
+        logout(oidc_domain, oidc_client_id, profile, credential_helper)?;
+
+        println!("Logout successful.");
+
+        Ok(())
     }
 }
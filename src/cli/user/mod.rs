@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod info;
+mod list;
 mod login;
 mod logout;
 mod register;
@@ -12,8 +13,8 @@ use clap::Subcommand;
 pub enum Subcommands {
     #[clap(hide = true)]
     Info(info::Options),
+    List(list::Options),
     Login(login::Options),
-    #[clap(hide = true)]
     Logout(logout::Options),
     Register(register::Options),
 }
@@ -22,6 +23,7 @@ impl Subcommands {
     pub fn dispatch(self) -> anyhow::Result<()> {
         match self {
             Self::Info(cmd) => cmd.execute(),
+            Self::List(cmd) => cmd.execute(),
             Self::Login(cmd) => cmd.execute(),
             Self::Logout(cmd) => cmd.execute(),
             Self::Register(cmd) => cmd.execute(),
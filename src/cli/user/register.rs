@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::drawbridge::{client, get_token, login, UserSpec};
+use crate::drawbridge::{client, get_token, login, UserSpec, DEFAULT_PROFILE};
+use crate::host_config::HostConfig;
 
 use std::ffi::OsString;
 
@@ -23,7 +24,9 @@ pub struct Options {
     insecure_auth_token: Option<String>,
     #[clap(long, env = "ENARX_CREDENTIAL_HELPER")]
     credential_helper: Option<OsString>,
-    #[clap(long, default_value = "https://auth.profian.com/")]
+    #[clap(long, env = "ENARX_PROFILE", default_value = DEFAULT_PROFILE)]
+    profile: String,
+    #[clap(long, env = "ENARX_OIDC_DOMAIN", default_value = HostConfig::get().oidc_domain())]
     oidc_domain: Url,
     #[clap(long, default_value = "4NuaJxkQv8EZBeJKE56R57gKJbxrTLG2")]
     oidc_client_id: String,
@@ -39,17 +42,24 @@ impl Options {
             oidc_client_id,
             ref spec,
             ref credential_helper,
+            ref profile,
         } = self;
 
         // If we don't find a token saved locally, initiate an interactive login
-        let token = match get_token(oidc_domain, insecure_auth_token, credential_helper) {
+        let token = match get_token(oidc_domain, profile, insecure_auth_token, credential_helper) {
             Ok(token) => token,
-            _ => login(oidc_domain, oidc_client_id.clone(), credential_helper)?,
+            _ => login(
+                oidc_domain,
+                oidc_client_id.clone(),
+                profile,
+                credential_helper,
+            )?,
         };
 
         let cl = client(
             &spec.host,
             oidc_domain,
+            profile,
             &Some(token.clone()),
             ca_bundle,
             credential_helper,
@@ -1,9 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod ignore;
+mod signature;
+
+pub use ignore::Ignore;
+pub use signature::{sign_entry, verify_entry, SigningKey, VerifyingKey};
+
 use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{stderr, Write};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use std::thread::spawn;
@@ -17,10 +25,24 @@ use oauth2::basic::BasicClient;
 use oauth2::devicecode::StandardDeviceAuthorizationResponse;
 use oauth2::ureq::http_client;
 use oauth2::url::Url;
-use oauth2::{AuthType, AuthUrl, ClientId, DeviceAuthorizationUrl, Scope, TokenResponse, TokenUrl};
-use rustls::{Certificate, RootCertStore};
+use oauth2::{
+    AuthType, AuthUrl, ClientId, DeviceAuthorizationUrl, RefreshToken, RevocationUrl, Scope,
+    StandardRevocableToken, TokenResponse, TokenUrl,
+};
+use rustls::{Certificate, OwnedTrustAnchor, RootCertStore};
+
+use crate::host_config::HostConfig;
+
+/// Name of the credential profile used when none is specified.
+pub const DEFAULT_PROFILE: &str = "default";
 
-const DEFAULT_HOST: &str = "store.profian.com";
+/// Keyring service under which access tokens are stored.
+const KEYRING_SERVICE: &str = "enarx";
+
+/// Keyring service under which refresh tokens are stored, if the provider issued one.
+///
+/// Credential helpers store them as `enarx-refresh-token/<name>` instead.
+const KEYRING_REFRESH_SERVICE: &str = "enarx-refresh-token";
 
 #[derive(Debug)]
 pub struct UserSpec {
@@ -95,52 +117,155 @@ fn parse_repo(slug: &str) -> anyhow::Result<(String, &str, &str)> {
 }
 
 fn parse_user(slug: &str) -> (String, &str) {
-    let (host, user) = slug.rsplit_once('/').unwrap_or((DEFAULT_HOST, slug));
+    let (host, user) = slug
+        .rsplit_once('/')
+        .unwrap_or((HostConfig::get().host(), slug));
     (host.to_string(), user)
 }
 
+/// Returns the name under which the credentials of `profile` for `oidc_domain` are stored.
+///
+/// The default profile uses the bare OpenID Connect domain host, which keeps credentials
+/// saved before profiles were introduced working. Named profiles are stored as `profile@host`.
+fn credential_name(oidc_domain: &Url, profile: &str) -> anyhow::Result<String> {
+    let host = oidc_domain
+        .host_str()
+        .ok_or_else(|| anyhow!("invalid OpenID Connect domain"))?;
+    if profile.is_empty()
+        || profile
+            .chars()
+            .any(|c| c == '@' || c == '/' || c.is_whitespace() || c.is_control())
+    {
+        bail!("Invalid profile name: {profile:?}")
+    }
+    if profile == DEFAULT_PROFILE {
+        Ok(host.into())
+    } else {
+        Ok(format!("{profile}@{host}"))
+    }
+}
+
+/// Profiles with locally saved credentials, indexed by OpenID Connect domain host.
+pub type Profiles = BTreeMap<String, BTreeSet<String>>;
+
+/// Path of the index of profiles with saved credentials.
+///
+/// Neither the platform keyring nor credential helpers can enumerate entries,
+/// so `enarx` keeps track of the profiles it has logged in with on its own.
+fn profiles_path() -> anyhow::Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("enarx").join("profiles.toml"))
+        .context("Failed to determine user configuration directory")
+}
+
+/// Read the index of profiles with saved credentials.
+pub fn profiles() -> anyhow::Result<Profiles> {
+    let path = profiles_path()?;
+    match fs::read_to_string(&path) {
+        Ok(profiles) => toml::from_str(&profiles)
+            .with_context(|| format!("Failed to parse profile index at `{}`", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Profiles::new()),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to read profile index at `{}`", path.display()))
+        }
+    }
+}
+
+fn update_profiles(update: impl FnOnce(&mut Profiles)) -> anyhow::Result<()> {
+    let path = profiles_path()?;
+    let mut profiles = profiles()?;
+    update(&mut profiles);
+    profiles.retain(|_, names| !names.is_empty());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory `{}`", dir.display()))?;
+    }
+    fs::write(&path, toml::to_string(&profiles)?)
+        .with_context(|| format!("Failed to write profile index at `{}`", path.display()))
+}
+
+/// Returns the name under which a credential helper stores the refresh token of `name`.
+fn helper_refresh_name(name: &str) -> String {
+    format!("{KEYRING_REFRESH_SERVICE}/{name}")
+}
+
+/// Store `secret` under `name` with the credential helper.
+fn insert_helper(helper: impl AsRef<OsStr>, name: &str, secret: &str) -> anyhow::Result<()> {
+    let mut helper = Command::new(helper)
+        .stdin(Stdio::piped())
+        .arg("insert")
+        .arg(name)
+        .spawn()
+        .context("Failed to spawn credential helper command")?;
+    let mut stdin = helper.stdin.take().context("Failed to open stdin")?;
+    let secret = secret.to_string();
+    spawn(move || {
+        stdin
+            .write_all(secret.as_bytes())
+            .context("Failed to write secret to credential helper stdin")
+    })
+    .join()
+    .expect("Failed to join stdin pipe thread")?;
+    let output = helper
+        .wait()
+        .context("Failed to wait for credential helper to exit")?;
+    if output.success() {
+        Ok(())
+    } else if let Some(code) = output.code() {
+        bail!("Credential helper failed with exit code {code}")
+    } else {
+        bail!("Credential helper was killed")
+    }
+}
+
+fn run_helper(helper: impl AsRef<OsStr>, mode: &str, name: &str) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new(helper)
+        .arg(mode)
+        .arg(name)
+        .output()
+        .context("Failed to execute credential helper")?;
+    stderr()
+        .write_all(&output.stderr)
+        .context("Failed to write stderr")?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else if let Some(code) = output.status.code() {
+        bail!("Credential helper failed with exit code {code}")
+    } else {
+        bail!("Credential helper was killed")
+    }
+}
+
 pub fn get_token(
     oidc_domain: &impl Borrow<Url>,
+    profile: &str,
     provided_token: &Option<impl AsRef<str>>,
     helper: &Option<impl AsRef<OsStr>>,
 ) -> anyhow::Result<String> {
-    let oidc_domain = oidc_domain
-        .borrow()
-        .host_str()
-        .ok_or_else(|| anyhow!("invalid OpenID Connect domain"))?;
+    let name = credential_name(oidc_domain.borrow(), profile)?;
     if let Some(token) = provided_token {
         Ok(token.as_ref().into())
     } else if let Some(helper) = helper {
-        let output = Command::new(helper)
-            .arg("show")
-            .arg(oidc_domain)
-            .output()
-            .context("Failed to execute credential helper")?;
-        stderr()
-            .write_all(&output.stderr)
-            .context("Failed to write stderr")?;
-        if output.status.success() {
-            String::from_utf8(output.stdout).context("Credential helper stdout is not valid UTF-8")
-        } else if let Some(code) = output.status.code() {
-            bail!("Credential helper failed with exit code {code}")
-        } else {
-            bail!("Credential helper was killed")
-        }
+        let token = run_helper(helper, "show", &name)?;
+        String::from_utf8(token).context("Credential helper stdout is not valid UTF-8")
     } else {
-        keyring::Entry::new("enarx", oidc_domain)
+        keyring::Entry::new(KEYRING_SERVICE, &name)
             .get_password()
-            .context("Failed to read credentials from keyring")
+            .with_context(|| {
+                format!("Failed to read credentials for profile `{profile}` from keyring")
+            })
     }
 }
 
 pub fn client(
     host: &str,
     oidc_domain: &impl Borrow<Url>,
+    profile: &str,
     insecure_token: &Option<String>,
     ca_bundle: &Option<Utf8PathBuf>,
     helper: &Option<impl AsRef<OsStr>>,
 ) -> anyhow::Result<Client> {
-    let token = get_token(oidc_domain, insecure_token, helper)?;
+    let token = get_token(oidc_domain, profile, insecure_token, helper)?;
 
     let url = format!("https://{host}");
 
@@ -150,23 +275,7 @@ pub fn client(
     );
 
     if let Some(ca_bundle_path) = ca_bundle {
-        cl = cl.roots({
-            let mut roots = RootCertStore::empty();
-
-            let ca_bundle_file = File::open(ca_bundle_path)?;
-
-            rustls_pemfile::certs(&mut std::io::BufReader::new(ca_bundle_file))
-                .unwrap()
-                .into_iter()
-                .map(Certificate)
-                .try_for_each(|ref cert| {
-                    roots
-                        .add(cert)
-                        .with_context(|| format!("Failed to add root certificate: {cert:?}"))
-                })?;
-
-            roots
-        });
+        cl = cl.roots(roots(ca_bundle_path)?);
     }
 
     let cl = cl
@@ -177,27 +286,86 @@ pub fn client(
     Ok(cl)
 }
 
-pub fn login(
-    oidc_domain: &impl Borrow<Url>,
-    oidc_client_id: String,
-    helper: &Option<impl AsRef<OsStr>>,
-) -> anyhow::Result<String> {
-    let oidc_domain = oidc_domain.borrow();
+/// Construct an unauthenticated client, which can only access public repositories.
+///
+/// The host is contacted over `scheme`, trusting the WebPKI roots and the
+/// certificates in `ca_bundle`, like the package fetch of a deployment does.
+pub fn public_client(
+    scheme: &str,
+    host: &str,
+    ca_bundle: &Option<Utf8PathBuf>,
+) -> anyhow::Result<Client> {
+    let url = format!("{scheme}://{host}");
+
+    let mut cl = Client::builder(
+        url.parse()
+            .with_context(|| format!("Failed to parse URL: {url}"))?,
+    );
+
+    if let Some(ca_bundle_path) = ca_bundle {
+        let mut roots = roots(ca_bundle_path)?;
+        roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        cl = cl.roots(roots);
+    }
+
+    cl.build().context("Failed to build client")
+}
+
+/// Load the root certificates of the PEM-encoded bundle at `ca_bundle_path`.
+fn roots(ca_bundle_path: &Utf8PathBuf) -> anyhow::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+
+    let ca_bundle_file = File::open(ca_bundle_path)?;
+
+    rustls_pemfile::certs(&mut std::io::BufReader::new(ca_bundle_file))
+        .unwrap()
+        .into_iter()
+        .map(Certificate)
+        .try_for_each(|ref cert| {
+            roots
+                .add(cert)
+                .with_context(|| format!("Failed to add root certificate: {cert:?}"))
+        })?;
+
+    Ok(roots)
+}
+
+fn oauth_client(oidc_domain: &Url, oidc_client_id: String) -> anyhow::Result<BasicClient> {
     let dev_auth_url = DeviceAuthorizationUrl::new(format!("{oidc_domain}oauth/device/code"))
         .context("Failed to construct device authorization URL")?;
     let auth_url = AuthUrl::new(format!("{oidc_domain}authorize"))
         .context("Failed to construct authorization URL")?;
     let token_url = TokenUrl::new(format!("{oidc_domain}oauth/token"))
         .context("Failed to construct token URL")?;
+    let revocation_url = RevocationUrl::new(format!("{oidc_domain}oauth/revoke"))
+        .context("Failed to construct revocation URL")?;
 
-    let client = BasicClient::new(
+    Ok(BasicClient::new(
         ClientId::new(oidc_client_id),
         None,
         auth_url,
         Some(token_url),
     )
     .set_auth_type(AuthType::RequestBody)
-    .set_device_authorization_url(dev_auth_url);
+    .set_device_authorization_url(dev_auth_url)
+    .set_revocation_uri(revocation_url))
+}
+
+pub fn login(
+    oidc_domain: &impl Borrow<Url>,
+    oidc_client_id: String,
+    profile: &str,
+    helper: &Option<impl AsRef<OsStr>>,
+) -> anyhow::Result<String> {
+    let oidc_domain = oidc_domain.borrow();
+    let name = credential_name(oidc_domain, profile)?;
+    let client = oauth_client(oidc_domain, oidc_client_id)?;
 
     let details: StandardDeviceAuthorizationResponse = client
         .exchange_device_code()
@@ -222,47 +390,102 @@ pub fn login(
         .context("Failed to exchange device code for a token")?;
 
     // TODO: graceful timeout, so that users are not forced to Ctrl+C if the server errors
-    let oidc_domain = oidc_domain
-        .borrow()
-        .host_str()
-        .ok_or_else(|| anyhow!("invalid OpenID Connect domain"))?;
     let secret = res.access_token().secret();
     if let Some(helper) = helper {
-        let mut helper = Command::new(helper)
-            .stdin(Stdio::piped())
-            .arg("insert")
-            .arg(oidc_domain)
-            .spawn()
-            .context("Failed to spawn credential helper command")?;
-        let mut stdin = helper.stdin.take().context("Failed to open stdin")?;
-        let secret = secret.clone();
-        spawn(move || {
-            stdin
-                .write_all(secret.as_bytes())
-                .context("Failed to write secret to credential helper stdin")
-        })
-        .join()
-        .expect("Failed to join stdin pipe thread")?;
-        let output = helper
-            .wait()
-            .context("Failed to wait for credential helper to exit")?;
-        if !output.success() {
-            if let Some(code) = output.code() {
-                bail!("Credential helper failed with exit code {code}")
-            } else {
-                bail!("Credential helper was killed")
-            }
+        insert_helper(helper, &name, secret)?;
+        if let Some(refresh) = res.refresh_token() {
+            insert_helper(helper, &helper_refresh_name(&name), refresh.secret())
+                .context("Failed to save refresh token")?;
         }
     } else {
-        keyring::Entry::new("enarx", oidc_domain)
+        keyring::Entry::new(KEYRING_SERVICE, &name)
             .set_password(secret)
             .context("Failed to save user credentials")?;
+        if let Some(refresh) = res.refresh_token() {
+            keyring::Entry::new(KEYRING_REFRESH_SERVICE, &name)
+                .set_password(refresh.secret())
+                .context("Failed to save refresh token")?;
+        }
     }
+    update_profiles(|profiles| {
+        profiles
+            .entry(oidc_domain.host_str().unwrap_or_default().into())
+            .or_default()
+            .insert(profile.into());
+    })?;
     println!("Credentials saved locally.");
 
     Ok(secret.to_string())
 }
 
+pub fn logout(
+    oidc_domain: &impl Borrow<Url>,
+    oidc_client_id: String,
+    profile: &str,
+    helper: &Option<impl AsRef<OsStr>>,
+) -> anyhow::Result<()> {
+    let oidc_domain = oidc_domain.borrow();
+    let name = credential_name(oidc_domain, profile)?;
+
+    if let Some(helper) = helper {
+        // Credentials saved without a refresh token, e.g. by older versions,
+        // make the helper fail to show one, which is not an error.
+        let refresh_name = helper_refresh_name(&name);
+        if let Ok(token) = run_helper(helper, "show", &refresh_name) {
+            let token =
+                String::from_utf8(token).context("Credential helper stdout is not valid UTF-8")?;
+            revoke(oidc_domain, oidc_client_id, token)?;
+            run_helper(helper, "erase", &refresh_name).context("Failed to delete refresh token")?;
+        }
+        run_helper(helper, "erase", &name)?;
+    } else {
+        let refresh = keyring::Entry::new(KEYRING_REFRESH_SERVICE, &name);
+        match refresh.get_password() {
+            Ok(token) => {
+                revoke(oidc_domain, oidc_client_id, token)?;
+                refresh
+                    .delete_password()
+                    .context("Failed to delete refresh token")?;
+            }
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e).context("Failed to read refresh token from keyring"),
+        }
+
+        match keyring::Entry::new(KEYRING_SERVICE, &name).delete_password() {
+            Ok(()) => {}
+            Err(keyring::Error::NoEntry) => {
+                bail!("No credentials saved for profile `{profile}` on {oidc_domain}")
+            }
+            Err(e) => return Err(e).context("Failed to delete user credentials"),
+        }
+    }
+    update_profiles(|profiles| {
+        if let Some(names) = profiles.get_mut(oidc_domain.host_str().unwrap_or_default()) {
+            names.remove(profile);
+        }
+    })?;
+    println!("Credentials deleted locally.");
+
+    Ok(())
+}
+
+/// Revoke the refresh token `token`.
+///
+/// Revocation is best-effort, as the local credentials are deleted regardless,
+/// so only a warning is printed if the identity provider fails to revoke it.
+fn revoke(oidc_domain: &Url, oidc_client_id: String, token: String) -> anyhow::Result<()> {
+    let revoked = oauth_client(oidc_domain, oidc_client_id)?
+        .revoke_token(StandardRevocableToken::RefreshToken(RefreshToken::new(
+            token,
+        )))
+        .context("Failed to construct token revocation request")?
+        .request(http_client);
+    if let Err(e) = revoked {
+        eprintln!("Warning: failed to revoke refresh token: {e}");
+    }
+    Ok(())
+}
+
 const INTERVAL: Duration = Duration::from_secs(3);
 
 /// This function controls how often the final step of the device flow will
@@ -277,3 +500,28 @@ const INTERVAL: Duration = Duration::from_secs(3);
 fn poll_delay(_: Duration) {
     std::thread::sleep(INTERVAL);
 }
+
+#[cfg(test)]
+mod test {
+    use super::{credential_name, helper_refresh_name, DEFAULT_PROFILE};
+
+    #[test]
+    fn profile_credential_names() {
+        let domain = "https://auth.profian.com/".parse().unwrap();
+        assert_eq!(
+            credential_name(&domain, DEFAULT_PROFILE).unwrap(),
+            "auth.profian.com"
+        );
+        assert_eq!(
+            credential_name(&domain, "org").unwrap(),
+            "org@auth.profian.com"
+        );
+        assert!(credential_name(&domain, "").is_err());
+        assert!(credential_name(&domain, "a@b").is_err());
+        assert!(credential_name(&domain, "a/b").is_err());
+        assert_eq!(
+            helper_refresh_name("org@auth.profian.com"),
+            "enarx-refresh-token/org@auth.profian.com"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! `.enarxignore` handling for package publication.
+//!
+//! The file format is a subset of `.gitignore`:
+//! - blank lines and lines starting with `#` are ignored
+//! - `*` matches anything except `/`, `?` matches any single character except `/`
+//!   and `**` matches anything, including `/`
+//! - a pattern ending with `/` only matches directories
+//! - a pattern containing a `/` anywhere but at its end is matched against the
+//!   full path relative to the package root, otherwise against the file name only
+//! - a pattern starting with `!` re-includes paths excluded by a previous pattern
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Name of the file listing paths to exclude from a package.
+pub const IGNORE_FILE: &str = ".enarxignore";
+
+#[derive(Debug)]
+struct Pattern {
+    glob: String,
+    negate: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// A parsed `.enarxignore` file.
+#[derive(Debug, Default)]
+pub struct Ignore(Vec<Pattern>);
+
+impl Ignore {
+    /// Read the ignore file from the package root directory at `dir`, if there is one.
+    pub fn load(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = dir.as_ref().join(IGNORE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(s) => Ok(Self::parse(&s)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read `{}`", path.display())),
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        Self(
+            s.lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| {
+                    let (negate, line) = match line.strip_prefix('!') {
+                        Some(line) => (true, line),
+                        None => (false, line),
+                    };
+                    let (dir_only, line) = match line.strip_suffix('/') {
+                        Some(line) => (true, line),
+                        None => (false, line),
+                    };
+                    let anchored = line.contains('/');
+                    Pattern {
+                        glob: line.trim_start_matches('/').into(),
+                        negate,
+                        dir_only,
+                        anchored,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether `path`, relative to the package root and `/`-separated, is excluded.
+    pub fn excludes(&self, path: &str, is_dir: bool) -> bool {
+        if path == IGNORE_FILE {
+            return true;
+        }
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.0
+            .iter()
+            .rev()
+            .filter(|p| is_dir || !p.dir_only)
+            .find(|p| {
+                let target = if p.anchored { path } else { name };
+                glob(p.glob.as_bytes(), target.as_bytes())
+            })
+            .map_or(false, |p| !p.negate)
+    }
+}
+
+fn glob(pat: &[u8], s: &[u8]) -> bool {
+    match pat {
+        [] => s.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob(rest, s)
+                || s.iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c == b'/')
+                    .any(|(i, _)| glob(rest, &s[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=s.len()).any(|i| glob(rest, &s[i..])),
+        [b'*', rest @ ..] => {
+            let end = s.iter().position(|&c| c == b'/').unwrap_or(s.len());
+            (0..=end).any(|i| glob(rest, &s[i..]))
+        }
+        [b'?', rest @ ..] => matches!(s, [c, tail @ ..] if *c != b'/' && glob(rest, tail)),
+        [p, rest @ ..] => matches!(s, [c, tail @ ..] if c == p && glob(rest, tail)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ignore;
+
+    #[test]
+    fn excludes() {
+        let ignore = Ignore::parse(
+            "# comment\n\
+             \n\
+             *.log\n\
+             target/\n\
+             /build\n\
+             docs/**/*.md\n\
+             !important.log\n",
+        );
+
+        assert!(ignore.excludes(".enarxignore", false));
+        assert!(ignore.excludes("debug.log", false));
+        assert!(ignore.excludes("assets/debug.log", false));
+        assert!(!ignore.excludes("important.log", false));
+        assert!(!ignore.excludes("debug.log.bin", false));
+
+        assert!(ignore.excludes("target", true));
+        assert!(ignore.excludes("assets/target", true));
+        assert!(!ignore.excludes("target", false));
+
+        assert!(ignore.excludes("build", true));
+        assert!(ignore.excludes("build", false));
+        assert!(!ignore.excludes("assets/build", true));
+
+        assert!(ignore.excludes("docs/README.md", false));
+        assert!(ignore.excludes("docs/a/b/README.md", false));
+        assert!(!ignore.excludes("README.md", false));
+
+        assert!(!ignore.excludes("main.wasm", false));
+        assert!(!ignore.excludes("Enarx.toml", false));
+        assert!(!ignore.excludes("model/weights.bin", false));
+    }
+}
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! JWS signatures over Drawbridge tag entries.
+//!
+//! A signed tag stores the JSON-serialized root tree entry of the package as
+//! the payload of a flattened JWS. Verification accepts both the flattened
+//! and the general JWS JSON serialization.
+
+use std::fs;
+
+use anyhow::{anyhow, bail, Context};
+use camino::Utf8Path;
+use drawbridge_client::types::{TagEntry, TreeEntry};
+use pkcs8::der::Document;
+use pkcs8::{ObjectIdentifier, SubjectPublicKeyInfo};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, UnparsedPublicKey, VerificationAlgorithm,
+    ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, ECDSA_P384_SHA384_FIXED,
+    ECDSA_P384_SHA384_FIXED_SIGNING, ED25519,
+};
+use rustls_pemfile::Item;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const OID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const OID_SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const OID_SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const OID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// Signature algorithms supported for package signing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Es256,
+    Es384,
+    EdDsa,
+}
+
+impl Algorithm {
+    /// The JWA name of the algorithm.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Es256 => "ES256",
+            Self::Es384 => "ES384",
+            Self::EdDsa => "EdDSA",
+        }
+    }
+
+    fn verification(self) -> &'static dyn VerificationAlgorithm {
+        match self {
+            Self::Es256 => &ECDSA_P256_SHA256_FIXED,
+            Self::Es384 => &ECDSA_P384_SHA384_FIXED,
+            Self::EdDsa => &ED25519,
+        }
+    }
+}
+
+/// A private key used to sign packages.
+pub enum SigningKey {
+    Ecdsa(Algorithm, EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+}
+
+impl SigningKey {
+    /// Load a PEM- or DER-encoded PKCS#8 P-256, P-384 or Ed25519 private key.
+    pub fn load(path: &Utf8Path) -> anyhow::Result<Self> {
+        let buf = fs::read(path).with_context(|| format!("Failed to read signing key `{path}`"))?;
+        let der = match rustls_pemfile::read_one(&mut buf.as_slice()) {
+            Ok(Some(Item::PKCS8Key(der))) => der,
+            Ok(Some(_)) => bail!("Signing key `{path}` is not a PKCS#8 private key"),
+            _ => buf,
+        };
+        Self::from_pkcs8(&der).with_context(|| format!("Failed to parse signing key `{path}`"))
+    }
+
+    fn from_pkcs8(der: &[u8]) -> anyhow::Result<Self> {
+        if let Ok(key) = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, der) {
+            Ok(Self::Ecdsa(Algorithm::Es256, key))
+        } else if let Ok(key) = EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, der) {
+            Ok(Self::Ecdsa(Algorithm::Es384, key))
+        } else {
+            Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+                .map(Self::Ed25519)
+                .map_err(|_| anyhow!("unsupported key, expected a P-256, P-384 or Ed25519 key"))
+        }
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        match self {
+            Self::Ecdsa(alg, _) => *alg,
+            Self::Ed25519(_) => Algorithm::EdDsa,
+        }
+    }
+
+    fn sign(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Ecdsa(_, key) => key
+                .sign(&SystemRandom::new(), msg)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|_| anyhow!("failed to sign message")),
+            Self::Ed25519(key) => Ok(key.sign(msg).as_ref().to_vec()),
+        }
+    }
+
+    #[cfg(test)]
+    fn verifying_key(&self) -> VerifyingKey {
+        let key = match self {
+            Self::Ecdsa(_, key) => key.public_key().as_ref().to_vec(),
+            Self::Ed25519(key) => key.public_key().as_ref().to_vec(),
+        };
+        VerifyingKey {
+            alg: self.algorithm(),
+            key,
+        }
+    }
+}
+
+/// A public key of a trusted package publisher.
+#[derive(Debug)]
+pub struct VerifyingKey {
+    alg: Algorithm,
+    key: Vec<u8>,
+}
+
+impl VerifyingKey {
+    /// Load a PEM-encoded SubjectPublicKeyInfo P-256, P-384 or Ed25519 public key.
+    pub fn load(path: impl AsRef<Utf8Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let pem = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read public key `{path}`"))?;
+        let (label, doc) = Document::from_pem(&pem)
+            .map_err(|e| anyhow!("{e}"))
+            .with_context(|| format!("Failed to decode public key `{path}`"))?;
+        if label != "PUBLIC KEY" {
+            bail!("Expected a `PUBLIC KEY` PEM block in `{path}`, found `{label}`")
+        }
+        Self::from_spki(doc.as_bytes())
+            .with_context(|| format!("Failed to parse public key `{path}`"))
+    }
+
+    fn from_spki(der: &[u8]) -> anyhow::Result<Self> {
+        let spki = SubjectPublicKeyInfo::try_from(der).map_err(|e| anyhow!("{e}"))?;
+        let alg = match spki.algorithm.oid {
+            oid if oid == OID_ED25519 => Algorithm::EdDsa,
+            oid if oid == OID_EC_PUBLIC_KEY => match spki.algorithm.parameters_oid() {
+                Ok(curve) if curve == OID_SECP256R1 => Algorithm::Es256,
+                Ok(curve) if curve == OID_SECP384R1 => Algorithm::Es384,
+                _ => bail!("unsupported elliptic curve, expected P-256 or P-384"),
+            },
+            oid => bail!("unsupported public key algorithm: {oid}"),
+        };
+        Ok(Self {
+            alg,
+            key: spki.subject_public_key.to_vec(),
+        })
+    }
+
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        UnparsedPublicKey::new(self.alg.verification(), &self.key)
+            .verify(msg, sig)
+            .is_ok()
+    }
+}
+
+fn encode(buf: impl AsRef<[u8]>) -> String {
+    base64::encode_config(buf, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(buf: &str) -> anyhow::Result<Vec<u8>> {
+    base64::decode_config(buf, base64::URL_SAFE_NO_PAD).context("Invalid base64url encoding")
+}
+
+/// Sign `payload` and return it as a flattened JWS.
+fn sign(key: &SigningKey, payload: &impl Serialize) -> anyhow::Result<Value> {
+    let protected = encode(json!({ "alg": key.algorithm().name() }).to_string());
+    let payload = encode(serde_json::to_vec(payload)?);
+    let signature = encode(key.sign(format!("{protected}.{payload}").as_bytes())?);
+    Ok(json!({
+        "protected": protected,
+        "payload": payload,
+        "signature": signature,
+    }))
+}
+
+/// Verify that `jws` carries a valid signature by one of the `trusted` keys
+/// and return its decoded payload.
+fn verify(jws: &Value, trusted: &[VerifyingKey]) -> anyhow::Result<Value> {
+    let payload = jws
+        .get("payload")
+        .and_then(Value::as_str)
+        .context("Package is not signed")?;
+    let signatures = match jws.get("signatures") {
+        Some(Value::Array(signatures)) => signatures.iter().collect(),
+        _ => vec![jws],
+    };
+    for signature in signatures {
+        let protected = signature
+            .get("protected")
+            .and_then(Value::as_str)
+            .context("Signature is missing a protected header")?;
+        let header: Value = serde_json::from_slice(&decode(protected)?)
+            .context("Failed to parse protected header")?;
+        let alg = header.get("alg").and_then(Value::as_str);
+        let signature = signature
+            .get("signature")
+            .and_then(Value::as_str)
+            .context("Signature is missing")?;
+        let signature = decode(signature)?;
+        let msg = format!("{protected}.{payload}");
+        if trusted
+            .iter()
+            .filter(|key| Some(key.alg.name()) == alg)
+            .any(|key| key.verify(msg.as_bytes(), &signature))
+        {
+            return serde_json::from_slice(&decode(payload)?).context("Failed to parse payload");
+        }
+    }
+    bail!("Package is not signed by any of the trusted keys")
+}
+
+/// Sign the root tree entry of a package and return the signed tag entry.
+pub fn sign_entry(key: &SigningKey, entry: &TreeEntry) -> anyhow::Result<TagEntry> {
+    serde_json::from_value(sign(key, entry)?).context("Failed to construct signed tag entry")
+}
+
+/// Verify that the tag `entry` is signed by one of the `trusted` keys and
+/// return the signed root tree entry of the package.
+pub fn verify_entry(entry: &TagEntry, trusted: &[VerifyingKey]) -> anyhow::Result<TreeEntry> {
+    let payload = verify(&serde_json::to_value(entry)?, trusted)?;
+    serde_json::from_value(payload).context("Failed to parse signed tree entry")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<SigningKey> {
+        let rng = SystemRandom::new();
+        let p256 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let p384 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, &rng).unwrap();
+        let ed25519 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        [p256, p384, ed25519]
+            .iter()
+            .map(|der| SigningKey::from_pkcs8(der.as_ref()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sign_verify() {
+        let keys = keys();
+        let algs: Vec<_> = keys.iter().map(SigningKey::algorithm).collect();
+        assert_eq!(algs, [Algorithm::Es256, Algorithm::Es384, Algorithm::EdDsa]);
+
+        let payload = json!({ "digest": "test", "length": 42 });
+        for (i, key) in keys.iter().enumerate() {
+            let jws = sign(key, &payload).unwrap();
+            assert_eq!(verify(&jws, &[key.verifying_key()]).unwrap(), payload);
+
+            // Any other key must be rejected
+            let others: Vec<_> = keys
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, key)| key.verifying_key())
+                .collect();
+            assert!(verify(&jws, &others).is_err());
+
+            // The general serialization is accepted as well
+            let general = json!({
+                "payload": jws["payload"],
+                "signatures": [{
+                    "protected": jws["protected"],
+                    "signature": jws["signature"],
+                }],
+            });
+            assert_eq!(verify(&general, &[key.verifying_key()]).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn tampered_payload() {
+        let key = &keys()[0];
+        let mut jws = sign(key, &json!({ "length": 42 })).unwrap();
+        jws["payload"] = encode(json!({ "length": 43 }).to_string()).into();
+        assert!(verify(&jws, &[key.verifying_key()]).is_err());
+    }
+
+    #[test]
+    fn signed_entry() {
+        let key = &keys()[0];
+        let entry: TreeEntry = serde_json::from_value(json!({
+            "digest": { "sha-256": base64::encode([0u8; 32]) },
+            "length": 42,
+            "type": "application/vnd.drawbridge.directory.v1+json",
+        }))
+        .unwrap();
+
+        let tag = sign_entry(key, &entry).unwrap();
+        let signed = verify_entry(&tag, &[key.verifying_key()]).unwrap();
+        assert_eq!(
+            serde_json::to_value(signed.meta).unwrap(),
+            serde_json::to_value(entry.meta).unwrap()
+        );
+    }
+
+    #[test]
+    fn unsigned() {
+        let key = &keys()[0];
+        let entry = json!({ "digest": {}, "length": 42, "type": "application/json" });
+        assert!(verify(&entry, &[key.verifying_key()]).is_err());
+    }
+}
@@ -24,14 +24,18 @@
 #[cfg(enarx_with_shim)]
 pub mod exec_wasmtime;
 
-use crate::backend::{Backend, Command, Signatures};
+use crate::backend::metrics::{self, METRICS};
+use crate::backend::{Backend, Command, Keep, Signatures};
 
+use std::collections::BTreeMap;
 use std::convert::Into;
 use std::fs::File;
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
 #[cfg(unix)]
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
@@ -96,7 +100,12 @@ pub fn keep_exec(
     _gdblisten: Option<String>,
 ) -> anyhow::Result<libc::c_int> {
     let keep = backend.keep(shim.as_ref(), exec.as_ref(), signatures)?;
-    let mut thread = keep.clone().spawn()?.unwrap();
+    keep_run(keep, _gdblisten)
+}
+
+/// Runs an already built Keep until it exits.
+pub fn keep_run(keep: Arc<dyn Keep>, _gdblisten: Option<String>) -> anyhow::Result<libc::c_int> {
+    let mut thread = keep.spawn()?.unwrap();
     loop {
         match thread.enter(&_gdblisten)? {
             Command::Continue => (),
@@ -122,6 +131,28 @@ pub fn open_package(
     }
 }
 
+/// Host options of [`run_package`]
+pub struct RunOptions {
+    pub gdblisten: Option<String>,
+    pub ca_bundle: Option<String>,
+
+    /// Whether to allow sealing keys derived from a public value on backends
+    /// without a key of the hardware
+    pub insecure_keys: bool,
+
+    /// Content digest the root tree of a remote package must match
+    pub package_digest: Option<BTreeMap<String, String>>,
+
+    /// Time for the workload to exit after a shutdown signal
+    pub shutdown_grace: Duration,
+
+    /// Address to serve the metrics of the Keep on
+    pub metrics_listen: Option<String>,
+
+    /// Maximum guest memory of the Keep in bytes
+    pub memory: Option<u64>,
+}
+
 /// Runs a package.
 /// SAFETY: Panics if next free FD number is not equal to 3.
 /// In other words, callers must either close all files opened at runtime before calling this
@@ -129,15 +160,26 @@ pub fn open_package(
 #[cfg(windows)]
 pub fn run_package(
     backend: &dyn Backend,
+    shim: impl AsRef<[u8]>,
     exec: impl AsRef<[u8]>,
     _signatures: Option<Signatures>,
-    gdblisten: Option<String>,
+    options: RunOptions,
     package: impl FnOnce() -> Result<Package>,
 ) -> Result<i32> {
     let package = package()?;
-    let args = ExecArgs { package };
+    let args = ExecArgs {
+        ca_bundle: options.ca_bundle,
+        insecure_keys: options.insecure_keys,
+        package_digest: options.package_digest,
+        package,
+    };
     backend.set_args(args);
-    let exit_code = keep_exec(backend, backend.shim(), exec, None, gdblisten)?;
+    crate::backend::set_memory_limit(options.memory);
+    if let Some(addr) = options.metrics_listen.as_deref() {
+        metrics::serve(addr)?;
+    }
+    let exit_code = keep_exec(backend, shim, exec, None, options.gdblisten)?;
+    log::info!("keep exited with {exit_code}: {}", METRICS.summary());
     Ok(exit_code)
 }
 
@@ -148,16 +190,20 @@ pub fn run_package(
 #[cfg(unix)]
 pub fn run_package(
     backend: &dyn Backend,
+    shim: impl AsRef<[u8]>,
     exec: impl AsRef<[u8]>,
     signatures: Option<Signatures>,
-    gdblisten: Option<String>,
+    options: RunOptions,
     package: impl FnOnce() -> Result<Package>,
 ) -> Result<i32> {
     use std::io::Write;
-    use std::net::Shutdown;
     use std::os::unix::net::UnixStream;
     use std::thread;
 
+    // Shutdown signals are forwarded to the workload by a dedicated thread,
+    // so they must be blocked before any other thread is spawned.
+    let signals = block_shutdown_signals()?;
+
     let (exec_sock, mut host_sock) =
         UnixStream::pair().context("failed to create a Unix socket pair")?;
 
@@ -168,8 +214,13 @@ pub fn run_package(
     );
 
     let package = package()?;
-    let args =
-        toml::to_vec(&ExecArgs { package }).context("failed to encode exec-wasmtime arguments")?;
+    let args = toml::to_vec(&ExecArgs {
+        ca_bundle: options.ca_bundle,
+        insecure_keys: options.insecure_keys,
+        package_digest: options.package_digest,
+        package,
+    })
+    .context("failed to encode exec-wasmtime arguments")?;
 
     host_sock
         .set_nonblocking(true)
@@ -178,26 +229,142 @@ pub fn run_package(
         .set_write_timeout(Some(ARG_WRITE_TIMEOUT))
         .context("failed to set timeout on host socket")?;
 
+    // The socket stays open to forward shutdown requests, until exec-wasmtime
+    // closes its end because the workload does not listen for them.
+    let shutdown_sock = host_sock
+        .try_clone()
+        .context("failed to clone host socket")?;
+    let grace = options.shutdown_grace;
+    thread::spawn(move || forward_signals(signals, shutdown_sock, grace));
+    let listener_sock = host_sock
+        .try_clone()
+        .context("failed to clone host socket")?;
+    thread::spawn(move || watch_shutdown_listener(listener_sock));
+
     let exec_io = thread::spawn(move || {
-        host_sock
-            .write_all(&args)
-            .context("failed to write arguments to `wasmtime-exec`")?;
-        host_sock
-            .shutdown(Shutdown::Write)
-            .context("failed to shutdown write half of host's socket")?;
         // TODO: Read exec-wasmtime output from the socket
         host_sock
-            .shutdown(Shutdown::Read)
-            .context("failed to shutdown read half of host's socket")
+            .write_all(&(args.len() as u64).to_le_bytes())
+            .and_then(|()| host_sock.write_all(&args))
+            .context("failed to write arguments to `wasmtime-exec`")
     });
 
-    let exit_code = keep_exec(backend, backend.shim(), exec, signatures, gdblisten)?;
+    // Bound after the exec socket, which must be FD 3.
+    if let Some(addr) = options.metrics_listen.as_deref() {
+        metrics::serve(addr)?;
+    }
+
+    crate::backend::set_memory_limit(options.memory);
+    let exit_code = keep_exec(backend, shim, exec, signatures, options.gdblisten)?;
+    log::info!("keep exited with {exit_code}: {}", METRICS.summary());
     exec_io
         .join()
         .expect("failed to join exec-wasmtime I/O thread")?;
     Ok(exit_code)
 }
 
+/// Block `SIGTERM` and `SIGINT` in the calling thread and the threads it spawns.
+#[cfg(unix)]
+fn block_shutdown_signals() -> Result<libc::sigset_t> {
+    use std::mem::MaybeUninit;
+
+    let mut signals = MaybeUninit::uninit();
+    let signals = unsafe {
+        libc::sigemptyset(signals.as_mut_ptr());
+        libc::sigaddset(signals.as_mut_ptr(), libc::SIGTERM);
+        libc::sigaddset(signals.as_mut_ptr(), libc::SIGINT);
+        signals.assume_init()
+    };
+    match unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &signals, std::ptr::null_mut()) } {
+        0 => Ok(signals),
+        e => Err(std::io::Error::from_raw_os_error(e)).context("failed to block signals"),
+    }
+}
+
+/// The first shutdown signal received, or zero
+#[cfg(unix)]
+static SHUTDOWN_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Whether the workload may listen for shutdown requests
+#[cfg(unix)]
+static SHUTDOWN_LISTENING: AtomicBool = AtomicBool::new(true);
+
+/// Wait until exec-wasmtime closes its end of `host`, which it does if the
+/// workload has no `shutdown` file, and kill the Keep if a shutdown signal was
+/// already received.
+#[cfg(unix)]
+fn watch_shutdown_listener(host: std::os::unix::net::UnixStream) {
+    // exec-wasmtime never writes to the socket, so it only becomes readable
+    // once the other end is closed.
+    let mut fd = libc::pollfd {
+        fd: host.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    while unsafe { libc::poll(&mut fd, 1, -1) } < 0 {
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return;
+        }
+    }
+
+    log::debug!("the workload does not listen for shutdown requests");
+    SHUTDOWN_LISTENING.store(false, Ordering::SeqCst);
+    match SHUTDOWN_SIGNAL.load(Ordering::SeqCst) {
+        0 => (),
+        signal => {
+            log::warn!("the workload does not listen for shutdown requests, killing the Keep");
+            std::process::exit(128 + signal);
+        }
+    }
+}
+
+/// Forward the first shutdown signal to the workload and kill the Keep after
+/// `grace`, or right away on a second signal or if the workload does not
+/// listen for shutdown requests.
+#[cfg(unix)]
+fn forward_signals(
+    signals: libc::sigset_t,
+    mut host: std::os::unix::net::UnixStream,
+    grace: Duration,
+) {
+    use std::io::Write;
+    use std::net::Shutdown;
+
+    let wait = || {
+        let mut signal = 0;
+        unsafe { libc::sigwait(&signals, &mut signal) };
+        signal
+    };
+
+    let signal = wait();
+    let name = match signal {
+        libc::SIGINT => "SIGINT",
+        _ => "SIGTERM",
+    };
+    SHUTDOWN_SIGNAL.store(signal, Ordering::SeqCst);
+    if !SHUTDOWN_LISTENING.load(Ordering::SeqCst) {
+        log::info!("received {name}, killing the Keep as the workload does not listen for it");
+        std::process::exit(128 + signal);
+    }
+    log::info!("received {name}, asking the workload to shut down within {grace:?}");
+    if let Err(e) = host
+        .write_all(format!("{name}\n").as_bytes())
+        .and_then(|()| host.shutdown(Shutdown::Write))
+    {
+        log::warn!("failed to forward {name} to the workload: {e}");
+    }
+
+    std::thread::spawn(move || {
+        std::thread::sleep(grace);
+        log::warn!("workload did not shut down within {grace:?}, killing the Keep");
+        std::process::exit(128 + signal);
+    });
+
+    let signal = wait();
+    log::warn!("received another signal, killing the Keep");
+    std::process::exit(128 + signal);
+}
+
 #[cfg(test)]
 mod test {
     use super::{Exec, NilExec};
@@ -0,0 +1,473 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Host-wide `enarx` configuration.
+//!
+//! The configuration is read from `/etc/enarx/config.toml` and the user
+//! configuration directory, e.g. `~/.config/enarx/config.toml`, with values
+//! set in the latter taking precedence. Command-line options and environment
+//! variables take precedence over both.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use url::Url;
+
+/// Path of the system-wide configuration file.
+const SYSTEM_CONFIG: &str = "/etc/enarx/config.toml";
+
+/// Default package host.
+const DEFAULT_HOST: &str = "store.profian.com";
+
+/// Default OpenID Connect domain used to authenticate with the package host.
+const DEFAULT_OIDC_DOMAIN: &str = "https://auth.profian.com/";
+
+/// Default log output target.
+const DEFAULT_LOG_TARGET: &str = "stderr";
+
+/// Default log format.
+const DEFAULT_LOG_FORMAT: &str = "text";
+
+/// Backends which do not provide hardware isolation.
+const INSECURE_BACKENDS: &[&str] = &["nil", "kvm"];
+
+static CONFIG: OnceCell<HostConfig> = OnceCell::new();
+
+/// Logging settings.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct LogConfig {
+    /// Log filter, as in `--log-filter`.
+    filter: Option<String>,
+
+    /// Log output target, as in `--log-target`.
+    target: Option<String>,
+
+    /// Log format, as in `--log-format`.
+    format: Option<String>,
+}
+
+/// Policy for `enarx deploy`, which can be overridden with `--insecure`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct DeployPolicy {
+    /// Whether packages may be fetched over plain HTTP.
+    allow_http: Option<bool>,
+
+    /// Backends refused for remote packages.
+    insecure_backends: Option<Vec<String>>,
+}
+
+/// Contents of a single configuration file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    /// Default backend, as in `--backend`.
+    backend: Option<String>,
+
+    /// Package host used for packages, users and repositories without one.
+    host: Option<String>,
+
+    /// OpenID Connect domain, as in `--oidc-domain`.
+    oidc_domain: Option<String>,
+
+    /// Signature files to use for each backend, as in `--signatures`.
+    signatures: BTreeMap<String, PathBuf>,
+
+    /// Maximum memory of a Keep, as in `--memory`.
+    memory: Option<String>,
+
+    log: LogConfig,
+
+    deploy: DeployPolicy,
+}
+
+impl ConfigFile {
+    fn read(path: &Path) -> anyhow::Result<Option<Self>> {
+        let config = match fs::read_to_string(path) {
+            Ok(config) => config,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read configuration at `{}`", path.display())
+                })
+            }
+        };
+        let config: Self = toml::from_str(&config)
+            .with_context(|| format!("Failed to parse configuration at `{}`", path.display()))?;
+        if let Some(ref domain) = config.oidc_domain {
+            Url::parse(domain).with_context(|| {
+                format!(
+                    "Invalid `oidc-domain` in configuration at `{}`",
+                    path.display()
+                )
+            })?;
+        }
+        Ok(Some(config))
+    }
+}
+
+/// Where the value of a setting comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// An environment variable
+    Env(&'static str),
+
+    /// A configuration file
+    File(PathBuf),
+
+    /// The built-in default
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(name) => write!(f, "environment variable {name}"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Effective value of a setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    /// Name of the setting in the configuration file
+    pub name: String,
+
+    /// Effective value
+    pub value: toml::Value,
+
+    /// Where the value comes from
+    pub source: Source,
+}
+
+/// Host-wide configuration.
+#[derive(Debug, Default)]
+pub struct HostConfig {
+    /// Configuration files, from the highest to the lowest precedence.
+    files: Vec<(PathBuf, ConfigFile)>,
+}
+
+impl HostConfig {
+    /// Load the configuration files, so that they are used by [`HostConfig::get`].
+    ///
+    /// This must be called before parsing the command line, as the
+    /// configuration provides defaults for command-line options.
+    pub fn init() -> anyhow::Result<&'static Self> {
+        let config = Self::load()?;
+        Ok(CONFIG.get_or_init(|| config))
+    }
+
+    /// Returns the configuration loaded by [`HostConfig::init`], or the
+    /// defaults if none was loaded.
+    pub fn get() -> &'static Self {
+        CONFIG.get_or_init(Self::default)
+    }
+
+    /// Load the user and system configuration files, if present.
+    fn load() -> anyhow::Result<Self> {
+        let mut files = Vec::new();
+        let paths = user_config_path()
+            .into_iter()
+            .chain([PathBuf::from(SYSTEM_CONFIG)]);
+        for path in paths {
+            if let Some(file) = ConfigFile::read(&path)? {
+                files.push((path, file));
+            }
+        }
+        Ok(Self { files })
+    }
+
+    /// Returns the first value set in a configuration file and the file it is set in.
+    fn lookup<'a, T: 'a>(
+        &'a self,
+        field: impl Fn(&'a ConfigFile) -> Option<T>,
+    ) -> Option<(T, Source)> {
+        self.files
+            .iter()
+            .find_map(|(path, file)| field(file).map(|v| (v, Source::File(path.clone()))))
+    }
+
+    /// Default backend.
+    pub fn backend(&self) -> Option<&str> {
+        self.lookup(|f| f.backend.as_deref()).map(|(v, _)| v)
+    }
+
+    /// Package host used for specs without one.
+    pub fn host(&self) -> &str {
+        self.lookup(|f| f.host.as_deref())
+            .map_or(DEFAULT_HOST, |(v, _)| v)
+    }
+
+    /// OpenID Connect domain used to authenticate with the package host.
+    pub fn oidc_domain(&self) -> &str {
+        self.lookup(|f| f.oidc_domain.as_deref())
+            .map_or(DEFAULT_OIDC_DOMAIN, |(v, _)| v)
+    }
+
+    /// Signature file to use for the backend called `name`.
+    pub fn signatures(&self, name: &str) -> Option<&Path> {
+        self.lookup(|f| f.signatures.get(name))
+            .map(|(v, _)| v.as_path())
+    }
+
+    /// Maximum memory of a Keep, e.g. `2G`.
+    pub fn memory(&self) -> Option<&str> {
+        self.lookup(|f| f.memory.as_deref()).map(|(v, _)| v)
+    }
+
+    /// Log filter.
+    pub fn log_filter(&self) -> Option<&str> {
+        self.lookup(|f| f.log.filter.as_deref()).map(|(v, _)| v)
+    }
+
+    /// Log output target.
+    pub fn log_target(&self) -> &str {
+        self.lookup(|f| f.log.target.as_deref())
+            .map_or(DEFAULT_LOG_TARGET, |(v, _)| v)
+    }
+
+    /// Log format.
+    pub fn log_format(&self) -> &str {
+        self.lookup(|f| f.log.format.as_deref())
+            .map_or(DEFAULT_LOG_FORMAT, |(v, _)| v)
+    }
+
+    /// Whether packages may be fetched over plain HTTP, `false` by default.
+    pub fn allow_http(&self) -> bool {
+        self.lookup(|f| f.deploy.allow_http)
+            .map_or(false, |(v, _)| v)
+    }
+
+    /// Whether remote packages are refused on the backend called `name`,
+    /// by default those without hardware isolation.
+    pub fn is_insecure_backend(&self, name: &str) -> bool {
+        match self.lookup(|f| f.deploy.insecure_backends.as_ref()) {
+            Some((backends, _)) => backends.iter().any(|b| b == name),
+            None => INSECURE_BACKENDS.contains(&name),
+        }
+    }
+
+    /// Effective value and source of every setting, taking the environment into account.
+    pub fn settings(&self) -> Vec<Setting> {
+        fn setting(
+            name: impl Into<String>,
+            env: Option<&'static str>,
+            file: Option<(toml::Value, Source)>,
+            default: Option<toml::Value>,
+        ) -> Option<Setting> {
+            let env = env.and_then(|var| {
+                std::env::var(var)
+                    .ok()
+                    .map(|v| (v.into(), Source::Env(var)))
+            });
+            let (value, source) = env
+                .or(file)
+                .or_else(|| default.map(|v| (v, Source::Default)))?;
+            Some(Setting {
+                name: name.into(),
+                value,
+                source,
+            })
+        }
+        let string = |v: &str| toml::Value::from(v);
+
+        let mut settings = vec![
+            setting(
+                "backend",
+                Some("ENARX_BACKEND"),
+                self.lookup(|f| f.backend.as_deref().map(string)),
+                None,
+            ),
+            setting(
+                "host",
+                None,
+                self.lookup(|f| f.host.as_deref().map(string)),
+                Some(DEFAULT_HOST.into()),
+            ),
+            setting(
+                "oidc-domain",
+                Some("ENARX_OIDC_DOMAIN"),
+                self.lookup(|f| f.oidc_domain.as_deref().map(string)),
+                Some(DEFAULT_OIDC_DOMAIN.into()),
+            ),
+        ];
+
+        let mut backends: Vec<_> = self
+            .files
+            .iter()
+            .flat_map(|(_, f)| f.signatures.keys())
+            .collect();
+        backends.sort();
+        backends.dedup();
+        settings.extend(backends.into_iter().map(|name| {
+            setting(
+                format!("signatures.{name}"),
+                None,
+                self.lookup(|f| {
+                    f.signatures
+                        .get(name)
+                        .map(|path| path.to_string_lossy().as_ref().into())
+                }),
+                None,
+            )
+        }));
+
+        settings.extend([
+            setting(
+                "memory",
+                Some("ENARX_MEMORY"),
+                self.lookup(|f| f.memory.as_deref().map(string)),
+                None,
+            ),
+            setting(
+                "log.filter",
+                Some("ENARX_LOG"),
+                self.lookup(|f| f.log.filter.as_deref().map(string)),
+                None,
+            ),
+            setting(
+                "log.target",
+                None,
+                self.lookup(|f| f.log.target.as_deref().map(string)),
+                Some(DEFAULT_LOG_TARGET.into()),
+            ),
+            setting(
+                "log.format",
+                None,
+                self.lookup(|f| f.log.format.as_deref().map(string)),
+                Some(DEFAULT_LOG_FORMAT.into()),
+            ),
+            setting(
+                "deploy.allow-http",
+                None,
+                self.lookup(|f| f.deploy.allow_http.map(toml::Value::from)),
+                Some(false.into()),
+            ),
+            setting(
+                "deploy.insecure-backends",
+                None,
+                self.lookup(|f| f.deploy.insecure_backends.clone().map(toml::Value::from)),
+                Some(INSECURE_BACKENDS.to_vec().into()),
+            ),
+        ]);
+
+        settings.into_iter().flatten().collect()
+    }
+}
+
+/// Path of the user configuration file.
+fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("enarx").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, HostConfig, Source};
+
+    use std::path::PathBuf;
+
+    fn config(files: &[(&str, &str)]) -> HostConfig {
+        HostConfig {
+            files: files
+                .iter()
+                .map(|(path, file)| (PathBuf::from(path), toml::from_str(file).unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn deploy_policy() {
+        let default = HostConfig::default();
+        assert!(!default.allow_http());
+        assert!(default.is_insecure_backend("nil"));
+        assert!(default.is_insecure_backend("kvm"));
+        assert!(!default.is_insecure_backend("sgx"));
+
+        let config = config(&[
+            ("user", "[deploy]\nallow-http = false\n"),
+            (
+                "system",
+                r#"
+                [deploy]
+                allow-http = true
+                insecure-backends = ["nil"]
+                "#,
+            ),
+        ]);
+        assert!(!config.allow_http());
+        assert!(config.is_insecure_backend("nil"));
+        assert!(!config.is_insecure_backend("kvm"));
+
+        assert!(toml::from_str::<ConfigFile>("[deploy]\nallow-htp = true\n").is_err());
+    }
+
+    #[test]
+    fn precedence() {
+        let default = HostConfig::default();
+        assert_eq!(default.backend(), None);
+        assert_eq!(default.memory(), None);
+        assert_eq!(default.host(), "store.profian.com");
+        assert_eq!(default.oidc_domain(), "https://auth.profian.com/");
+        assert_eq!(default.log_target(), "stderr");
+
+        let config = config(&[
+            (
+                "user",
+                r#"
+                host = "localhost:8080"
+
+                [signatures]
+                sgx = "/home/user/sgx.json"
+                "#,
+            ),
+            (
+                "system",
+                r#"
+                backend = "sev"
+                host = "store.example.com"
+                memory = "2G"
+
+                [signatures]
+                sgx = "/etc/enarx/sgx.json"
+                sev = "/etc/enarx/sev.json"
+
+                [log]
+                filter = "info"
+                "#,
+            ),
+        ]);
+        assert_eq!(config.backend(), Some("sev"));
+        assert_eq!(config.host(), "localhost:8080");
+        assert_eq!(
+            config.signatures("sgx"),
+            Some(PathBuf::from("/home/user/sgx.json").as_path())
+        );
+        assert_eq!(
+            config.signatures("sev"),
+            Some(PathBuf::from("/etc/enarx/sev.json").as_path())
+        );
+        assert_eq!(config.signatures("kvm"), None);
+        assert_eq!(config.log_filter(), Some("info"));
+        assert_eq!(config.memory(), Some("2G"));
+
+        let settings = config.settings();
+        let host = settings.iter().find(|s| s.name == "host").unwrap();
+        assert_eq!(host.value.as_str(), Some("localhost:8080"));
+        assert_eq!(host.source, Source::File("user".into()));
+        let target = settings.iter().find(|s| s.name == "log.target").unwrap();
+        assert_eq!(target.source, Source::Default);
+        let sev = settings
+            .iter()
+            .find(|s| s.name == "signatures.sev")
+            .unwrap();
+        assert_eq!(sev.source, Source::File("system".into()));
+    }
+}
@@ -11,12 +11,15 @@ mod backend;
 mod cli;
 mod drawbridge;
 mod exec;
+mod host_config;
 #[cfg(enarx_with_shim)]
 mod protobuf;
 
 use clap::Parser;
 
 fn main() -> anyhow::Result<()> {
+    // The host configuration provides defaults for command-line options.
+    host_config::HostConfig::init()?;
     let app = cli::Options::parse();
     app.execute()
 }
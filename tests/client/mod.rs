@@ -116,6 +116,40 @@ async fn full() {
         let cmd = cmd!("enarx repo info {db_addr}/testuser/publicrepo");
         assert_eq!(cmd.success, true);
 
+        // test for failure when verifying an unsigned package
+        let cmd = cmd!(
+            "enarx package verify
+            --trusted-key {workspace_dir}/tests/client/testdata/signer.pub
+            {db_addr}/testuser/publicrepo:2.0.0"
+        );
+        assert_eq!(cmd.success, false);
+
+        // test for success when publishing a signed package
+        let cmd = cmd!(
+            "enarx package publish
+            --sign-key {workspace_dir}/tests/client/testdata/signer.key
+            {db_addr}/testuser/publicrepo:3.0.0
+            {workspace_dir}/tests/client/testdata/echo_server"
+        );
+        assert_eq!(cmd.success, true);
+
+        // test for success when verifying a signed package with the signer key
+        let cmd = cmd!(
+            "enarx package verify
+            --trusted-key {workspace_dir}/tests/client/testdata/client.pub
+            --trusted-key {workspace_dir}/tests/client/testdata/signer.pub
+            {db_addr}/testuser/publicrepo:3.0.0"
+        );
+        assert_eq!(cmd.success, true);
+
+        // test for failure when verifying a signed package with an untrusted key
+        let cmd = cmd!(
+            "enarx package verify
+            --trusted-key {workspace_dir}/tests/client/testdata/client.pub
+            {db_addr}/testuser/publicrepo:3.0.0"
+        );
+        assert_eq!(cmd.success, false);
+
         // TODO: fetch package
         // TODO: deploy package
     })
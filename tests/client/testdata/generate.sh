@@ -38,3 +38,8 @@ printf "\nGenerating Client Certificate\n"
 openssl x509 -req -days 9999 -CAcreateserial -CA ca.crt -CAkey ca.key -in client.csr -out client.crt -extfile client.conf -extensions client_crt
 printf "\nClient "
 openssl x509 -noout -text -in client.crt
+
+printf "\nGenerating package signing key\n"
+openssl genpkey -algorithm ed25519 -out signer.key
+openssl pkey -in signer.key -pubout -out signer.pub
+openssl pkey -in client.key -pubout -out client.pub
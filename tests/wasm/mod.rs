@@ -96,6 +96,7 @@ pub fn enarx_run<'a>(
     let out = enarx(
         |cmd| {
             cmd.args(vec![
+                "key",
                 "sign",
                 "--sgx-key",
                 "tests/data/sgx-test.key",